@@ -0,0 +1,622 @@
+use std::fmt;
+
+use crate::config::ConfigOverrides;
+
+/// Parsed command line, global flags and the command to run
+#[derive(Debug, PartialEq)]
+pub struct Cli {
+    pub overrides: ConfigOverrides,
+    /// Parse every snippet file again instead of using the index
+    pub rebuild_index: bool,
+    pub command: Command,
+}
+
+/// Commands ono can run, parsed from the command line arguments
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// Open the main TUI
+    Tui,
+    Config(ConfigCommand),
+    /// Interactively create the config file and the data directory
+    Setup,
+    /// Open the new snippet form populated with the clipboard content
+    Clip,
+    Add(AddArgs),
+    Import(ImportArgs),
+    Export(ExportArgs),
+    /// Commit, pull and push the data directory with git
+    Sync,
+    /// Run the snippet with the alias or title
+    Run(String),
+    Mv(MvArgs),
+    /// Show the top snippets, daily activity and snippets never used
+    Stats,
+}
+
+/// Arguments of `ono add`, the snippet is written without the form when title is given
+#[derive(Debug, Default, PartialEq)]
+pub struct AddArgs {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub alias: Option<String>,
+    pub tags: Vec<String>,
+    /// Command words, quoted where needed and joined with spaces, a single one is the whole
+    /// command. `-` reads the command from stdin
+    pub command: Vec<String>,
+    /// Save the command even if it looks like it contains secrets
+    pub allow_secrets: bool,
+}
+
+/// Arguments of `ono mv`
+#[derive(Debug, PartialEq)]
+pub struct MvArgs {
+    /// Alias or title of the snippet
+    pub name: String,
+    pub title: String,
+    /// Data directory or folder inside one to move the snippet to, by label or path
+    pub folder: Option<String>,
+}
+
+/// Arguments of `ono import`
+#[derive(Debug, PartialEq)]
+pub struct ImportArgs {
+    pub from: ImportFormat,
+    /// File or directory to import from
+    pub path: String,
+    /// Only report what would be imported
+    pub dry_run: bool,
+}
+
+/// Snippet managers ono imports from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportFormat {
+    Pet,
+    Navi,
+    Cheat,
+    Tldr,
+    Json,
+}
+
+/// Arguments of `ono export`
+#[derive(Debug, PartialEq)]
+pub struct ExportArgs {
+    pub format: ExportFormat,
+    /// Only snippets having every tag are exported
+    pub tags: Vec<String>,
+    /// Only snippets in the data directory with this path or label are exported
+    pub folder: Option<String>,
+    /// File to write to, stdout if not given
+    pub output: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Pet,
+    Navi,
+    MarkdownBundle,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ConfigCommand {
+    /// Print every effective config value along with its source
+    Show,
+    Get(String),
+    Set(String, String),
+    Edit,
+    Path,
+}
+
+/// Represents errors for parsing the command line arguments
+#[derive(Debug, PartialEq)]
+pub enum CliError {
+    MissingArgument(&'static str),
+    UnexpectedArgument(String),
+    UnknownCommand(String),
+    InvalidValue(&'static str, String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::MissingArgument(name) => write!(f, "missing argument <{name}>"),
+            CliError::UnexpectedArgument(arg) => write!(f, "unexpected argument `{arg}`"),
+            CliError::UnknownCommand(cmd) => write!(f, "unknown command `{cmd}`"),
+            CliError::InvalidValue(name, value) => {
+                write!(f, "invalid value `{value}` for <{name}>")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// Parse command line arguments, excluding the program name
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Cli, CliError> {
+    let (overrides, rebuild_index, args) = extract_global_flags(args)?;
+    let mut args = args.into_iter();
+
+    let command = match args.next().as_deref() {
+        None => Command::Tui,
+        Some("config") => Command::Config(parse_config_args(&mut args)?),
+        Some("setup") => Command::Setup,
+        Some("clip") => Command::Clip,
+        Some("add") => Command::Add(parse_add_args(&mut args)?),
+        Some("import") => Command::Import(parse_import_args(&mut args)?),
+        Some("export") => Command::Export(parse_export_args(&mut args)?),
+        Some("sync") => Command::Sync,
+        Some("stats") => Command::Stats,
+        Some("run") => Command::Run(args.next().ok_or(CliError::MissingArgument("name"))?),
+        Some("mv") => Command::Mv(parse_mv_args(&mut args)?),
+        Some(cmd) => return Err(CliError::UnknownCommand(cmd.to_string())),
+    };
+
+    match args.next() {
+        Some(arg) => Err(CliError::UnexpectedArgument(arg)),
+        None => Ok(Cli {
+            overrides,
+            rebuild_index,
+            command,
+        }),
+    }
+}
+
+/// Take out the global flags, they can be anywhere before `--`
+fn extract_global_flags<I: IntoIterator<Item = String>>(
+    args: I,
+) -> Result<(ConfigOverrides, bool, Vec<String>), CliError> {
+    let mut overrides = ConfigOverrides::default();
+    let mut rebuild_index = false;
+    let mut rest = vec![];
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+
+        let target = match flag {
+            "--config" => &mut overrides.config_file,
+            "--data-dir" => &mut overrides.data_dir,
+            "--rebuild-index" if inline_value.is_none() => {
+                rebuild_index = true;
+                continue;
+            }
+            "--" => {
+                rest.push(arg);
+                rest.extend(args);
+                break;
+            }
+            _ => {
+                rest.push(arg);
+                continue;
+            }
+        };
+
+        let value = inline_value.or_else(|| args.next());
+        *target = Some(value.ok_or(CliError::MissingArgument("path"))?);
+    }
+
+    Ok((overrides, rebuild_index, rest))
+}
+
+fn parse_config_args(args: &mut impl Iterator<Item = String>) -> Result<ConfigCommand, CliError> {
+    let mut required = |name| args.next().ok_or(CliError::MissingArgument(name));
+
+    let command = match required("subcommand").ok().as_deref() {
+        None => ConfigCommand::Show,
+        Some("get") => ConfigCommand::Get(required("key")?),
+        Some("set") => ConfigCommand::Set(required("key")?, required("value")?),
+        Some("edit") => ConfigCommand::Edit,
+        Some("path") => ConfigCommand::Path,
+        Some(cmd) => return Err(CliError::UnknownCommand(format!("config {cmd}"))),
+    };
+
+    Ok(command)
+}
+
+fn parse_add_args(args: &mut impl Iterator<Item = String>) -> Result<AddArgs, CliError> {
+    let mut add_args = AddArgs::default();
+
+    while let Some(arg) = args.next() {
+        let mut value = |name| args.next().ok_or(CliError::MissingArgument(name));
+        match arg.as_str() {
+            "--title" => add_args.title = Some(value("title")?),
+            "--description" => add_args.description = Some(value("description")?),
+            "--alias" => add_args.alias = Some(value("alias")?),
+            "--tag" => add_args.tags.push(value("tag")?),
+            "--allow-secrets" => add_args.allow_secrets = true,
+            "--" => add_args.command.extend(args.by_ref()),
+            flag if flag.starts_with("--") => {
+                return Err(CliError::UnexpectedArgument(flag.to_string()));
+            }
+            _ => add_args.command.push(arg),
+        }
+    }
+
+    if add_args.command.is_empty() && add_args.title.is_some() {
+        return Err(CliError::MissingArgument("command"));
+    }
+
+    Ok(add_args)
+}
+
+fn parse_mv_args(args: &mut impl Iterator<Item = String>) -> Result<MvArgs, CliError> {
+    let mut positional = vec![];
+    let mut folder = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--folder" => folder = Some(args.next().ok_or(CliError::MissingArgument("folder"))?),
+            flag if flag.starts_with("--") => {
+                return Err(CliError::UnexpectedArgument(flag.to_string()));
+            }
+            _ if positional.len() < 2 => positional.push(arg),
+            _ => return Err(CliError::UnexpectedArgument(arg)),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    Ok(MvArgs {
+        name: positional.next().ok_or(CliError::MissingArgument("name"))?,
+        title: positional
+            .next()
+            .ok_or(CliError::MissingArgument("new title"))?,
+        folder,
+    })
+}
+
+fn parse_import_args(args: &mut impl Iterator<Item = String>) -> Result<ImportArgs, CliError> {
+    let mut from = None;
+    let mut path = None;
+    let mut dry_run = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" => {
+                let format = args.next().ok_or(CliError::MissingArgument("format"))?;
+                from = Some(match format.as_str() {
+                    "pet" => ImportFormat::Pet,
+                    "navi" => ImportFormat::Navi,
+                    "cheat" => ImportFormat::Cheat,
+                    "tldr" => ImportFormat::Tldr,
+                    "json" => ImportFormat::Json,
+                    _ => return Err(CliError::InvalidValue("format", format)),
+                });
+            }
+            "--dry-run" => dry_run = true,
+            flag if flag.starts_with("--") => {
+                return Err(CliError::UnexpectedArgument(flag.to_string()));
+            }
+            _ if path.is_none() => path = Some(arg),
+            _ => return Err(CliError::UnexpectedArgument(arg)),
+        }
+    }
+
+    Ok(ImportArgs {
+        from: from.ok_or(CliError::MissingArgument("format"))?,
+        path: path.ok_or(CliError::MissingArgument("path"))?,
+        dry_run,
+    })
+}
+
+fn parse_export_args(args: &mut impl Iterator<Item = String>) -> Result<ExportArgs, CliError> {
+    let mut format = None;
+    let mut export_args = ExportArgs {
+        format: ExportFormat::Json,
+        tags: vec![],
+        folder: None,
+        output: None,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name| args.next().ok_or(CliError::MissingArgument(name));
+        match arg.as_str() {
+            "--format" => {
+                let value = value("format")?;
+                format = Some(match value.as_str() {
+                    "json" => ExportFormat::Json,
+                    "csv" => ExportFormat::Csv,
+                    "pet" => ExportFormat::Pet,
+                    "navi" => ExportFormat::Navi,
+                    "markdown-bundle" => ExportFormat::MarkdownBundle,
+                    _ => return Err(CliError::InvalidValue("format", value)),
+                });
+            }
+            "--tag" => export_args.tags.push(value("tag")?),
+            "--folder" => export_args.folder = Some(value("folder")?),
+            "--output" | "-o" => export_args.output = Some(value("path")?),
+            _ => return Err(CliError::UnexpectedArgument(arg)),
+        }
+    }
+
+    export_args.format = format.ok_or(CliError::MissingArgument("format"))?;
+    Ok(export_args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, CliError> {
+        parse_args(args.iter().map(|a| a.to_string())).map(|cli| cli.command)
+    }
+
+    #[test]
+    fn it_parses_no_args_as_tui() {
+        assert_eq!(parse(&[]), Ok(Command::Tui));
+    }
+
+    #[test]
+    fn it_parses_clip() {
+        assert_eq!(parse(&["clip"]), Ok(Command::Clip));
+    }
+
+    #[test]
+    fn it_parses_sync() {
+        assert_eq!(parse(&["sync"]), Ok(Command::Sync));
+    }
+
+    #[test]
+    fn it_parses_stats() {
+        assert_eq!(parse(&["stats"]), Ok(Command::Stats));
+    }
+
+    #[test]
+    fn it_parses_mv() {
+        assert_eq!(
+            parse(&["mv", "ls", "List files", "--folder", "team"]),
+            Ok(Command::Mv(MvArgs {
+                name: "ls".to_string(),
+                title: "List files".to_string(),
+                folder: Some("team".to_string()),
+            }))
+        );
+        assert_eq!(
+            parse(&["mv", "ls"]),
+            Err(CliError::MissingArgument("new title"))
+        );
+        assert_eq!(
+            parse(&["mv", "ls", "a", "b"]),
+            Err(CliError::UnexpectedArgument("b".to_string()))
+        );
+    }
+
+    #[test]
+    fn it_parses_run() {
+        assert_eq!(
+            parse(&["run", "Disk usage"]),
+            Ok(Command::Run("Disk usage".to_string()))
+        );
+        assert_eq!(parse(&["run"]), Err(CliError::MissingArgument("name")));
+    }
+
+    #[test]
+    fn it_parses_add() {
+        let test_cases = vec![
+            (
+                vec!["add", "df -h"],
+                AddArgs {
+                    command: vec!["df -h".to_string()],
+                    ..Default::default()
+                },
+            ),
+            (
+                vec![
+                    "add", "--title", "prune", "--alias", "dp", "--tag", "docker", "--tag",
+                    "cleanup", "--", "docker", "system", "prune", "--all",
+                ],
+                AddArgs {
+                    title: Some("prune".to_string()),
+                    alias: Some("dp".to_string()),
+                    tags: vec!["docker".to_string(), "cleanup".to_string()],
+                    command: ["docker", "system", "prune", "--all"]
+                        .map(String::from)
+                        .to_vec(),
+                    ..Default::default()
+                },
+            ),
+            (
+                vec!["add", "--title", "from stdin", "-"],
+                AddArgs {
+                    title: Some("from stdin".to_string()),
+                    command: vec!["-".to_string()],
+                    ..Default::default()
+                },
+            ),
+            (
+                vec!["add", "--title", "db", "--allow-secrets", "psql"],
+                AddArgs {
+                    title: Some("db".to_string()),
+                    command: vec!["psql".to_string()],
+                    allow_secrets: true,
+                    ..Default::default()
+                },
+            ),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(parse(&input), Ok(Command::Add(expected)));
+        }
+
+        assert_eq!(
+            parse(&["add", "--title", "no command"]),
+            Err(CliError::MissingArgument("command"))
+        );
+        assert_eq!(
+            parse(&["add", "--force", "ls"]),
+            Err(CliError::UnexpectedArgument("--force".to_string()))
+        );
+    }
+
+    #[test]
+    fn it_parses_import() {
+        assert_eq!(
+            parse(&["import", "--from", "navi", "--dry-run", "~/cheats"]),
+            Ok(Command::Import(ImportArgs {
+                from: ImportFormat::Navi,
+                path: "~/cheats".to_string(),
+                dry_run: true,
+            }))
+        );
+
+        let test_cases = vec![
+            (
+                vec!["import", "pet.toml"],
+                CliError::MissingArgument("format"),
+            ),
+            (
+                vec!["import", "--from", "pet"],
+                CliError::MissingArgument("path"),
+            ),
+            (
+                vec!["import", "--from", "fzf", "a"],
+                CliError::InvalidValue("format", "fzf".to_string()),
+            ),
+            (
+                vec!["import", "--from", "pet", "a", "b"],
+                CliError::UnexpectedArgument("b".to_string()),
+            ),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(parse(&input), Err(expected));
+        }
+    }
+
+    #[test]
+    fn it_parses_export() {
+        assert_eq!(
+            parse(&[
+                "export", "--format", "csv", "--tag", "git", "--folder", "team", "-o", "out.csv"
+            ]),
+            Ok(Command::Export(ExportArgs {
+                format: ExportFormat::Csv,
+                tags: vec!["git".to_string()],
+                folder: Some("team".to_string()),
+                output: Some("out.csv".to_string()),
+            }))
+        );
+
+        let test_cases = vec![
+            (vec!["export"], CliError::MissingArgument("format")),
+            (
+                vec!["export", "--format", "yaml"],
+                CliError::InvalidValue("format", "yaml".to_string()),
+            ),
+            (
+                vec!["export", "--format", "json", "extra"],
+                CliError::UnexpectedArgument("extra".to_string()),
+            ),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(parse(&input), Err(expected));
+        }
+    }
+
+    #[test]
+    fn it_parses_setup() {
+        assert_eq!(parse(&["setup"]), Ok(Command::Setup));
+        assert_eq!(
+            parse(&["setup", "now"]),
+            Err(CliError::UnexpectedArgument("now".to_string()))
+        );
+    }
+
+    #[test]
+    fn it_parses_config_commands() {
+        let test_cases = vec![
+            (vec!["config"], ConfigCommand::Show),
+            (vec!["config", "path"], ConfigCommand::Path),
+            (vec!["config", "edit"], ConfigCommand::Edit),
+            (
+                vec!["config", "get", "editor"],
+                ConfigCommand::Get("editor".to_string()),
+            ),
+            (
+                vec!["config", "set", "editor", "nvim"],
+                ConfigCommand::Set("editor".to_string(), "nvim".to_string()),
+            ),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(parse(&input), Ok(Command::Config(expected)));
+        }
+    }
+
+    #[test]
+    fn it_fails_on_invalid_config_commands() {
+        let test_cases = vec![
+            (vec!["config", "get"], CliError::MissingArgument("key")),
+            (
+                vec!["config", "set", "editor"],
+                CliError::MissingArgument("value"),
+            ),
+            (
+                vec!["config", "path", "extra"],
+                CliError::UnexpectedArgument("extra".to_string()),
+            ),
+            (
+                vec!["config", "foo"],
+                CliError::UnknownCommand("config foo".to_string()),
+            ),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(parse(&input), Err(expected));
+        }
+    }
+
+    #[test]
+    fn it_parses_global_flags() {
+        let test_cases = vec![
+            (vec!["--data-dir", "/tmp/ono"], None, Some("/tmp/ono")),
+            (vec!["--config=/tmp/ono.yaml"], Some("/tmp/ono.yaml"), None),
+            (
+                vec![
+                    "config",
+                    "--data-dir",
+                    "/tmp/ono",
+                    "--config",
+                    "/tmp/a.yaml",
+                ],
+                Some("/tmp/a.yaml"),
+                Some("/tmp/ono"),
+            ),
+        ];
+
+        for (input, config_file, data_dir) in test_cases {
+            let args = input.iter().map(|a| a.to_string());
+            let cli = parse_args(args).unwrap();
+            assert_eq!(cli.overrides.config_file.as_deref(), config_file);
+            assert_eq!(cli.overrides.data_dir.as_deref(), data_dir);
+        }
+
+        assert_eq!(parse(&["--config"]), Err(CliError::MissingArgument("path")));
+    }
+
+    #[test]
+    fn it_parses_rebuild_index_flag() {
+        let parse_cli = |args: &[&str]| parse_args(args.iter().map(|a| a.to_string()));
+
+        assert!(parse_cli(&["--rebuild-index"]).unwrap().rebuild_index);
+        assert!(
+            parse_cli(&["add", "--rebuild-index", "ls"])
+                .unwrap()
+                .rebuild_index
+        );
+        assert!(!parse_cli(&[]).unwrap().rebuild_index);
+        assert_eq!(
+            parse_cli(&["add", "--", "--rebuild-index"])
+                .unwrap()
+                .command,
+            Command::Add(AddArgs {
+                command: vec!["--rebuild-index".to_string()],
+                ..Default::default()
+            })
+        );
+    }
+}
@@ -0,0 +1,353 @@
+//! System clipboard access.
+//! Uses OSC 52 escape sequences over SSH or when no clipboard helper is installed,
+//! otherwise the first external helper available at runtime.
+
+use std::{
+    env,
+    io::{self, IsTerminal, Write},
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use crossterm::terminal;
+
+use crate::os_helper;
+
+/// External clipboard helper
+struct Helper {
+    copy: &'static [&'static str],
+    paste: &'static [&'static str],
+    /// Environment variable that has to be set for the helper to work, e.g. a display server
+    requires_env: Option<&'static str>,
+}
+
+static HELPERS: [Helper; 5] = [
+    Helper {
+        copy: &["wl-copy"],
+        paste: &["wl-paste", "--no-newline"],
+        requires_env: Some("WAYLAND_DISPLAY"),
+    },
+    Helper {
+        copy: &["xclip", "-selection", "clipboard"],
+        paste: &["xclip", "-selection", "clipboard", "-o"],
+        requires_env: Some("DISPLAY"),
+    },
+    Helper {
+        copy: &["xsel", "--clipboard", "--input"],
+        paste: &["xsel", "--clipboard", "--output"],
+        requires_env: Some("DISPLAY"),
+    },
+    Helper {
+        copy: &["pbcopy"],
+        paste: &["pbpaste"],
+        requires_env: None,
+    },
+    Helper {
+        copy: &["clip.exe"],
+        paste: &["powershell.exe", "-NoProfile", "-Command", "Get-Clipboard"],
+        requires_env: None,
+    },
+];
+
+/// How long to wait for the terminal to answer the OSC 52 query
+const OSC52_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Read text from the clipboard
+pub fn read() -> io::Result<String> {
+    let helper = find_helper();
+    if let Some(helper) = helper
+        && !is_ssh_session()
+    {
+        return run_paste(helper);
+    }
+
+    match (osc52_read(), helper) {
+        (Ok(text), _) => Ok(text),
+        (Err(err), Some(helper)) => {
+            log::warn!("Reading clipboard with OSC 52 failed, using helper: {err:?}");
+            run_paste(helper)
+        }
+        (Err(err), None) => Err(err),
+    }
+}
+
+/// Whether writes go to an external helper, they can be slow and may be run in the background.
+/// Otherwise `write_osc52` is used.
+pub fn has_copy_helper() -> bool {
+    !is_ssh_session() && find_helper().is_some()
+}
+
+/// Write text to the clipboard with the external helper
+pub fn write_with_helper(text: &str) -> io::Result<()> {
+    match find_helper() {
+        Some(helper) if !is_ssh_session() => run_copy(helper, text),
+        _ => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "no clipboard helper available",
+        )),
+    }
+}
+
+fn is_ssh_session() -> bool {
+    env::var_os("SSH_TTY").is_some() || env::var_os("SSH_CONNECTION").is_some()
+}
+
+fn find_helper() -> Option<&'static Helper> {
+    HELPERS.iter().find(|helper| {
+        helper
+            .requires_env
+            .is_none_or(|name| env::var_os(name).is_some())
+            && os_helper::find_executable(helper.copy[0]).is_some()
+            && os_helper::find_executable(helper.paste[0]).is_some()
+    })
+}
+
+fn run_copy(helper: &Helper, text: &str) -> io::Result<()> {
+    log::info!("Copying to clipboard with {}", helper.copy[0]);
+    let mut child = Command::new(helper.copy[0])
+        .args(&helper.copy[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+
+    let status = child.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "{} exited with {status}",
+            helper.copy[0]
+        )))
+    }
+}
+
+fn run_paste(helper: &Helper) -> io::Result<String> {
+    log::info!("Reading clipboard with {}", helper.paste[0]);
+    let output = Command::new(helper.paste[0])
+        .args(&helper.paste[1..])
+        .stderr(Stdio::null())
+        .output()?;
+
+    if !output.status.success() {
+        let message = format!("{} exited with {}", helper.paste[0], output.status);
+        return Err(io::Error::other(message));
+    }
+
+    // powershell adds a trailing new line
+    let text = String::from_utf8_lossy(&output.stdout);
+    Ok(text.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Write text to the clipboard with OSC 52, the terminal sets it.
+/// Has to be called from the thread drawing the TUI so it isn't written in the middle of a frame.
+pub fn write_osc52(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    if !stdout.is_terminal() {
+        return Err(io::Error::other("stdout is not a terminal"));
+    }
+
+    let sequence = format!("\x1b]52;c;{}\x07", base64_encode(text.as_bytes()));
+    stdout.write_all(wrap_for_tmux(&sequence).as_bytes())?;
+    stdout.flush()
+}
+
+/// tmux only passes escape sequences to the outer terminal if they are wrapped
+fn wrap_for_tmux(sequence: &str) -> String {
+    if env::var_os("TMUX").is_some() {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence.to_string()
+    }
+}
+
+/// Ask the terminal for the clipboard content.
+/// The query is followed by a status report request, every terminal answers that one
+/// so the reader knows when to stop even if OSC 52 query isn't supported.
+fn osc52_read() -> io::Result<String> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err(io::Error::other("not running in a terminal"));
+    }
+
+    let was_raw = terminal::is_raw_mode_enabled()?;
+    if !was_raw {
+        terminal::enable_raw_mode()?;
+    }
+
+    let result = query_terminal();
+
+    if !was_raw {
+        terminal::disable_raw_mode()?;
+    }
+
+    let response = result?;
+    parse_osc52_response(&response)
+        .ok_or_else(|| io::Error::other("terminal doesn't support reading the clipboard"))
+}
+
+fn query_terminal() -> io::Result<Vec<u8>> {
+    let mut stdout = io::stdout();
+    stdout.write_all(wrap_for_tmux("\x1b]52;c;?\x07").as_bytes())?;
+    stdout.write_all(b"\x1b[5n")?;
+    stdout.flush()?;
+
+    read_response(Instant::now() + OSC52_TIMEOUT)
+}
+
+/// Read stdin until the status report answer, polled on this thread so nothing is left
+/// reading keystrokes after the terminal fails to answer in time
+#[cfg(unix)]
+fn read_response(deadline: Instant) -> io::Result<Vec<u8>> {
+    let mut response = vec![];
+    while !response.ends_with(b"\x1b[0n") {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let mut fds = libc::pollfd {
+            fd: libc::STDIN_FILENO,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout = libc::c_int::try_from(remaining.as_millis()).unwrap_or(libc::c_int::MAX);
+        // SAFETY: `fds` is a single valid pollfd that outlives the call
+        match unsafe { libc::poll(&mut fds, 1, timeout) } {
+            0 => {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "terminal didn't respond",
+                ));
+            }
+            ready if ready < 0 => {
+                let err = io::Error::last_os_error();
+                if err.kind() != io::ErrorKind::Interrupted {
+                    return Err(err);
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        // one byte at a time, keystrokes after the answer are left for the caller
+        let mut byte = 0u8;
+        // SAFETY: reads at most one byte into `byte`
+        match unsafe { libc::read(libc::STDIN_FILENO, (&raw mut byte).cast(), 1) } {
+            1 => response.push(byte),
+            0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+            _ => {
+                let err = io::Error::last_os_error();
+                if err.kind() != io::ErrorKind::Interrupted {
+                    return Err(err);
+                }
+            }
+        }
+    }
+
+    Ok(response)
+}
+
+/// Without a way to poll stdin a blocked read couldn't be stopped, so the clipboard
+/// isn't read from the terminal
+#[cfg(not(unix))]
+fn read_response(_deadline: Instant) -> io::Result<Vec<u8>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "reading the clipboard from the terminal isn't supported on this platform",
+    ))
+}
+
+/// Get the text from `ESC ] 52 ; c ; <base64> BEL` response, also accepts `ESC \` terminator
+fn parse_osc52_response(response: &[u8]) -> Option<String> {
+    let response = String::from_utf8_lossy(response);
+    let start = response.find("\x1b]52;")? + 5;
+    let payload = &response[start..];
+    let payload = &payload[payload.find(';')? + 1..];
+    let end = payload.find(['\x07', '\x1b'])?;
+
+    let decoded = base64_decode(&payload[..end])?;
+    String::from_utf8(decoded).ok()
+}
+
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(input: &[u8]) -> String {
+    let mut result = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let idx = (n >> (18 - i * 6)) & 0x3f;
+                result.push(BASE64_CHARS[idx as usize] as char);
+            } else {
+                result.push('=');
+            }
+        }
+    }
+    result
+}
+
+fn base64_decode(input: &str) -> Option<Vec<u8>> {
+    let values: Vec<u32> = input
+        .trim_end_matches('=')
+        .bytes()
+        .map(|c| BASE64_CHARS.iter().position(|&b| b == c).map(|p| p as u32))
+        .collect::<Option<_>>()?;
+
+    let mut result = Vec::with_capacity(values.len() * 3 / 4);
+    for chunk in values.chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, v)| acc | (v << (18 - i * 6)));
+        let bytes = n.to_be_bytes();
+        result.extend_from_slice(&bytes[1..chunk.len()]);
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_encodes_and_decodes_base64() {
+        let test_cases = vec![
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("docker ps -a", "ZG9ja2VyIHBzIC1h"),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(base64_encode(input.as_bytes()), expected);
+            assert_eq!(base64_decode(expected).unwrap(), input.as_bytes());
+        }
+
+        assert_eq!(base64_decode("not base64!"), None);
+    }
+
+    #[test]
+    fn it_parses_osc52_response() {
+        let test_cases = vec![
+            (&b"\x1b]52;c;bHMgLWFs\x07\x1b[0n"[..], Some("ls -al")),
+            (&b"\x1b]52;c;bHMgLWFs\x1b\\\x1b[0n"[..], Some("ls -al")),
+            (&b"\x1b[0n"[..], None),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(parse_osc52_response(input).as_deref(), expected);
+        }
+    }
+}
@@ -0,0 +1,12 @@
+//! Implementations of the non-interactive ono commands
+
+pub mod add;
+pub mod config;
+pub mod export;
+pub mod import;
+pub mod mv;
+pub mod prompt;
+pub mod run;
+pub mod setup;
+pub mod stats;
+pub mod sync;
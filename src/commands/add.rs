@@ -0,0 +1,136 @@
+use std::io::{self, IsTerminal, Read};
+
+use color_eyre::{
+    Result,
+    eyre::{bail, eyre},
+};
+
+use crate::{
+    cli::AddArgs,
+    commands::prompt::confirm,
+    config::OnoConfig,
+    store::{self, Duplicate, NewSnippet, secret},
+};
+
+/// Write the snippet without opening the TUI
+pub fn run(args: AddArgs, ono_config: &OnoConfig) -> Result<()> {
+    let data_dir = ono_config
+        .writable_data_dir()
+        .ok_or_else(|| eyre!("there is no writable data directory"))?;
+    let allow_secrets = args.allow_secrets;
+    let can_ask = args.command != ["-"] && io::stdin().is_terminal() && io::stderr().is_terminal();
+    let mut new_snippet = to_new_snippet(args)?;
+
+    let loaded = store::load_snippets(&ono_config.data_dirs);
+    for warning in &loaded.warnings {
+        eprintln!("Couldn't load {warning}");
+    }
+    let snippets = loaded.snippets;
+    match store::find_duplicate(&snippets, &new_snippet) {
+        Some(Duplicate::Title(existing)) => {
+            return Err(eyre!(
+                "a snippet titled `{}` already exists at {}",
+                existing.title,
+                existing.path.display()
+            ));
+        }
+        Some(Duplicate::Alias(existing)) => {
+            return Err(eyre!(
+                "alias `{}` is already used by `{}`",
+                new_snippet.alias.as_deref().unwrap_or_default(),
+                existing.title
+            ));
+        }
+        None => {}
+    }
+
+    // checked before anything is written, a saved secret may already be synced
+    let secrets = secret::find_secrets(&new_snippet.command);
+    if !secrets.is_empty() && !allow_secrets {
+        for secret in &secrets {
+            eprintln!(
+                "The command may contain {}, it can be replaced with <{}>",
+                secret.kind.description(),
+                secret.name
+            );
+        }
+
+        let question = "Replace them with placeholders?";
+        if !can_ask || !confirm(&mut io::stdin().lock(), &mut io::stderr(), question, true)? {
+            bail!("the snippet wasn't saved, use --allow-secrets to save it with the secrets");
+        }
+        new_snippet.command = secret::replace_with_placeholders(&new_snippet.command, &secrets);
+    }
+
+    std::fs::create_dir_all(data_dir)?;
+    let snippet = store::create_snippet(data_dir, &new_snippet)?;
+    println!("{}", snippet.path.display());
+
+    Ok(())
+}
+
+/// Convert the arguments to a new snippet, reads the command from stdin if it's `-`
+pub fn to_new_snippet(args: AddArgs) -> io::Result<NewSnippet> {
+    let command = if args.command == ["-"] {
+        let mut command = String::new();
+        io::stdin().read_to_string(&mut command)?;
+        command
+    } else {
+        join_command(&args.command)
+    };
+
+    Ok(NewSnippet {
+        title: args.title.unwrap_or_default(),
+        description: args.description.unwrap_or_default(),
+        alias: args.alias,
+        tags: args.tags,
+        command,
+    })
+}
+
+/// Join the command words the shell split, quoting the ones it would split again or
+/// interpret, e.g. `grep "a b" file`. A single word is the whole command, `ono add "ls | less"`.
+fn join_command(words: &[String]) -> String {
+    match words {
+        [command] => command.clone(),
+        _ => words
+            .iter()
+            .map(|word| quote_word(word))
+            .collect::<Vec<String>>()
+            .join(" "),
+    }
+}
+
+/// Word in single quotes unless it only has characters no shell treats specially
+fn quote_word(word: &str) -> String {
+    let is_plain = |c: char| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c);
+    if !word.is_empty() && word.chars().all(is_plain) {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_quotes_command_words() {
+        let test_cases = vec![
+            (vec!["ls", "-al", "~/src"], "ls -al '~/src'"),
+            (vec!["grep", "a b", "file"], "grep 'a b' file"),
+            (
+                vec!["echo", "it's", "$HOME", ""],
+                r"echo 'it'\''s' '$HOME' ''",
+            ),
+            (vec!["git", "log", "--format=%h"], "git log --format=%h"),
+            (vec!["ls | less"], "ls | less"),
+        ];
+
+        for (words, expected) in test_cases {
+            let words: Vec<String> = words.into_iter().map(String::from).collect();
+            assert_eq!(join_command(&words), expected, "words: {words:?}");
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use color_eyre::{Result, eyre::eyre};
+
+use crate::{
+    cli::ConfigCommand,
+    config::{self, CONFIG_KEYS, OnoConfig, OnoConfigError},
+    os_helper,
+};
+
+pub fn run(command: ConfigCommand, ono_config: &OnoConfig) -> Result<()> {
+    match command {
+        ConfigCommand::Show => show(ono_config),
+        ConfigCommand::Get(key) => get(ono_config, &key)?,
+        ConfigCommand::Set(key, value) => config::set_config_value(&ono_config.path, &key, &value)?,
+        ConfigCommand::Edit => edit(ono_config)?,
+        ConfigCommand::Path => println!("{}", ono_config.path.display()),
+    }
+
+    Ok(())
+}
+
+/// Print every config value along with where it comes from
+fn show(ono_config: &OnoConfig) {
+    let width = CONFIG_KEYS.iter().map(|k| k.len()).max().unwrap_or(0);
+    for key in CONFIG_KEYS {
+        let value = ono_config.get(key).unwrap_or("<not set>");
+        let source = ono_config
+            .source(key)
+            .map(|s| format!(" ({s})"))
+            .unwrap_or_default();
+
+        println!("{key:width$}  {value}{source}");
+    }
+
+    let source = ono_config
+        .source("data_dirs")
+        .map(|s| format!(" ({s})"))
+        .unwrap_or_default();
+    for (idx, data_dir) in ono_config.data_dirs.iter().enumerate() {
+        let key = if idx == 0 { "data_dirs" } else { "" };
+        let source = if idx == 0 { source.as_str() } else { "" };
+        println!("{key:width$}  {data_dir}{source}");
+    }
+
+    for (idx, rule) in ono_config.danger_rules.iter().enumerate() {
+        let key = if idx == 0 { "danger_rules" } else { "" };
+        println!("{key:width$}  {} ({})", rule.pattern, rule.reason);
+    }
+}
+
+fn get(ono_config: &OnoConfig, key: &str) -> Result<(), OnoConfigError> {
+    if key == "data_dirs" {
+        ono_config
+            .data_dirs
+            .iter()
+            .for_each(|d| println!("{}", d.path));
+        return Ok(());
+    }
+
+    if !CONFIG_KEYS.contains(&key) {
+        return Err(OnoConfigError::UnknownKey(key.to_string()));
+    }
+
+    if let Some(value) = ono_config.get(key) {
+        println!("{value}");
+    }
+
+    Ok(())
+}
+
+fn edit(ono_config: &OnoConfig) -> Result<()> {
+    let path = &ono_config.path;
+    if !path.exists() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, "")?;
+    }
+
+    let editor = ono_config
+        .editor
+        .as_deref()
+        .ok_or_else(|| eyre!("no editor configured"))?;
+
+    let status = os_helper::open_in_editor(editor, path)?;
+    if !status.success() {
+        return Err(eyre!("editor exited with {status}"));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,303 @@
+//! `ono export`, writes snippets in formats other tools read
+
+use std::{fs, path::Path};
+
+use color_eyre::Result;
+
+use crate::{
+    cli::{ExportArgs, ExportFormat},
+    config::OnoConfig,
+    store::{self, Snippet},
+};
+
+/// Export the snippets matching the filters to the output file or stdout
+pub fn run(args: ExportArgs, ono_config: &OnoConfig) -> Result<()> {
+    let loaded = store::load_snippets(&ono_config.data_dirs);
+    for warning in &loaded.warnings {
+        eprintln!("Couldn't load {warning}");
+    }
+
+    let folder = args.folder.as_deref().map(|f| {
+        let path = Path::new(f);
+        (
+            f,
+            fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()),
+        )
+    });
+    let snippets: Vec<&Snippet> = loaded
+        .snippets
+        .iter()
+        .filter(|s| args.tags.iter().all(|tag| s.tags.contains(tag)))
+        .filter(|s| {
+            folder.as_ref().is_none_or(|(label, path)| {
+                s.source.as_deref() == Some(label)
+                    || fs::canonicalize(&s.path).is_ok_and(|p| p.starts_with(path))
+            })
+        })
+        .collect();
+
+    let content = match args.format {
+        ExportFormat::Json => to_json(&snippets),
+        ExportFormat::Csv => to_csv(&snippets),
+        ExportFormat::Pet => to_pet(&snippets),
+        ExportFormat::Navi => to_navi(&snippets),
+        ExportFormat::MarkdownBundle => to_markdown_bundle(&snippets),
+    };
+
+    match args.output {
+        Some(output) => {
+            fs::write(&output, content)?;
+            eprintln!("Exported {} snippets to {output}", snippets.len());
+        }
+        None => print!("{content}"),
+    }
+
+    Ok(())
+}
+
+fn to_json(snippets: &[&Snippet]) -> String {
+    let quote = |value: &str| format!("\"{}\"", escape(value));
+    let quote_optional = |value: Option<&str>| value.map_or("null".to_string(), quote);
+
+    let items: Vec<String> = snippets
+        .iter()
+        .map(|s| {
+            let tags: Vec<String> = s.tags.iter().map(|t| quote(t)).collect();
+            let last_used = s.last_used.map(|d| d.to_rfc3339());
+            let fields = [
+                ("title", quote(&s.title)),
+                ("description", quote(&s.description)),
+                ("command", quote(&s.command)),
+                ("alias", quote_optional(s.alias.as_deref())),
+                ("tags", format!("[{}]", tags.join(", "))),
+                ("used", s.used.to_string()),
+                ("last_used", quote_optional(last_used.as_deref())),
+                ("source", quote_optional(s.source.as_deref())),
+                ("path", quote(&s.path.to_string_lossy())),
+            ];
+
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|(key, value)| format!("    \"{key}\": {value}"))
+                .collect();
+            format!("  {{\n{}\n  }}", fields.join(",\n"))
+        })
+        .collect();
+
+    if items.is_empty() {
+        return "[]\n".to_string();
+    }
+    format!("[\n{}\n]\n", items.join(",\n"))
+}
+
+/// One row per snippet with a header, tags are separated with commas
+fn to_csv(snippets: &[&Snippet]) -> String {
+    let mut content =
+        String::from("title,description,command,alias,tags,used,last_used,source,path\r\n");
+
+    for s in snippets {
+        let row = [
+            s.title.clone(),
+            s.description.clone(),
+            s.command.clone(),
+            s.alias.clone().unwrap_or_default(),
+            s.tags.join(", "),
+            s.used.to_string(),
+            s.last_used.map(|d| d.to_rfc3339()).unwrap_or_default(),
+            s.source.clone().unwrap_or_default(),
+            s.path.to_string_lossy().into_owned(),
+        ];
+        let row: Vec<String> = row.iter().map(|v| quote_csv(v)).collect();
+        content.push_str(&row.join(","));
+        content.push_str("\r\n");
+    }
+
+    content
+}
+
+fn quote_csv(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// pet snippet file, pet has no titles so the title becomes the description
+fn to_pet(snippets: &[&Snippet]) -> String {
+    snippets
+        .iter()
+        .map(|s| {
+            let tags: Vec<String> = s.tags.iter().map(|t| format!("\"{}\"", escape(t))).collect();
+            format!(
+                "[[snippets]]\n  description = \"{}\"\n  command = \"{}\"\n  tag = [{}]\n  output = \"\"\n\n",
+                escape(&s.title),
+                escape(&s.command),
+                tags.join(", ")
+            )
+        })
+        .collect()
+}
+
+/// navi cheat file, tags are written whenever they change
+fn to_navi(snippets: &[&Snippet]) -> String {
+    let mut content = String::new();
+    let mut tags: Option<&[String]> = None;
+
+    for s in snippets {
+        if tags != Some(&s.tags) {
+            if !content.is_empty() {
+                content.push('\n');
+            }
+            content.push_str(format!("% {}", s.tags.join(", ")).trim_end());
+            content.push('\n');
+            tags = Some(&s.tags);
+        }
+
+        content.push_str(&format!("\n# {}\n{}\n", one_line(&s.title), s.command));
+    }
+
+    content
+}
+
+/// Every snippet in a single markdown document
+fn to_markdown_bundle(snippets: &[&Snippet]) -> String {
+    let mut content = String::from("# Snippets\n");
+
+    for s in snippets {
+        content.push_str(&format!("\n## {}\n\n", one_line(&s.title)));
+        if !s.description.is_empty() {
+            content.push_str(&format!("{}\n\n", s.description));
+        }
+        if let Some(alias) = &s.alias {
+            content.push_str(&format!("- Alias: `{alias}`\n"));
+        }
+        if !s.tags.is_empty() {
+            content.push_str(&format!("- Tags: {}\n", s.tags.join(", ")));
+        }
+        if s.alias.is_some() || !s.tags.is_empty() {
+            content.push('\n');
+        }
+
+        // the fence must be longer than any backtick run in the command
+        let longest_run = s
+            .command
+            .split(|c| c != '`')
+            .map(str::len)
+            .max()
+            .unwrap_or_default();
+        let fence = "`".repeat(longest_run.max(2) + 1);
+        content.push_str(&format!("{fence}\n{}\n{fence}\n", s.command));
+    }
+
+    content
+}
+
+fn one_line(value: &str) -> String {
+    value.replace(['\r', '\n'], " ")
+}
+
+/// Escape the value for a JSON or TOML basic string, they share the escape sequences
+fn escape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if c.is_control() => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{commands::import::json, store::NewSnippet};
+    use std::path::PathBuf;
+
+    fn snippets() -> Vec<Snippet> {
+        vec![
+            Snippet {
+                title: "Find \"large\" files".to_string(),
+                description: "Biggest, first".to_string(),
+                command: "find . -size +100M\n\t| sort".to_string(),
+                alias: Some("big".to_string()),
+                tags: vec!["fs".to_string(), "find".to_string()],
+                path: PathBuf::from("/data/Find -large- files.md"),
+                used: 4,
+                ..Default::default()
+            },
+            Snippet {
+                title: "List".to_string(),
+                command: "ls ```".to_string(),
+                path: PathBuf::from("/team/List.md"),
+                source: Some("team".to_string()),
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn it_round_trips_json() {
+        let snippets = snippets();
+        let exported = to_json(&snippets.iter().collect::<Vec<_>>());
+
+        let imported = json::parse(&exported).unwrap();
+        assert_eq!(
+            imported[0],
+            NewSnippet {
+                title: snippets[0].title.clone(),
+                description: snippets[0].description.clone(),
+                alias: snippets[0].alias.clone(),
+                tags: snippets[0].tags.clone(),
+                command: snippets[0].command.clone(),
+            }
+        );
+        assert!(exported.contains("\"used\": 4"));
+        assert!(exported.contains("\"source\": \"team\""));
+        assert_eq!(to_json(&[]), "[]\n");
+    }
+
+    #[test]
+    fn it_exports_csv() {
+        let snippets = snippets();
+        let exported = to_csv(&snippets.iter().collect::<Vec<_>>());
+        let lines: Vec<&str> = exported.split("\r\n").collect();
+
+        assert_eq!(
+            lines[1],
+            "\"Find \"\"large\"\" files\",\"Biggest, first\",\"find . -size +100M\n\t| sort\",big,\"fs, find\",4,,,/data/Find -large- files.md"
+        );
+        assert_eq!(lines[2], "List,,ls ```,,,0,,team,/team/List.md");
+    }
+
+    #[test]
+    fn it_exports_pet_and_navi() {
+        let snippets = snippets();
+        let snippets: Vec<&Snippet> = snippets.iter().collect();
+
+        assert!(to_pet(&snippets).starts_with(
+            "[[snippets]]\n  description = \"Find \\\"large\\\" files\"\n  command = \"find . -size +100M\\n\\t| sort\"\n  tag = [\"fs\", \"find\"]\n"
+        ));
+        assert_eq!(
+            to_navi(&snippets),
+            "% fs, find\n\n# Find \"large\" files\nfind . -size +100M\n\t| sort\n\n%\n\n# List\nls ```\n"
+        );
+    }
+
+    #[test]
+    fn it_exports_markdown_bundle() {
+        let snippets = snippets();
+        let exported = to_markdown_bundle(&snippets.iter().collect::<Vec<_>>());
+
+        assert!(exported.contains(
+            "## Find \"large\" files\n\nBiggest, first\n\n- Alias: `big`\n- Tags: fs, find\n\n```\n"
+        ));
+        assert!(exported.ends_with("## List\n\n````\nls ```\n````\n"));
+    }
+}
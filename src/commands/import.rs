@@ -0,0 +1,227 @@
+//! `ono import`, converts snippets of other snippet managers to ono snippets
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::{Result, eyre::eyre};
+
+use crate::{
+    cli::{ImportArgs, ImportFormat},
+    config::OnoConfig,
+    store::{self, Duplicate, NewSnippet, Snippet},
+};
+
+mod cheat;
+pub mod json;
+mod navi;
+mod pet;
+mod tldr;
+
+/// Error in a file being imported
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(line: usize, message: impl Into<String>) -> Self {
+        ParseError {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Import the snippets into the writable data directory.
+/// Snippets whose title or alias is already used are reported and skipped.
+pub fn run(args: ImportArgs, ono_config: &OnoConfig) -> Result<()> {
+    let data_dir = ono_config
+        .writable_data_dir()
+        .ok_or_else(|| eyre!("there is no writable data directory"))?;
+    let imported = read_snippets(args.from, Path::new(&args.path))?;
+
+    let loaded = store::load_snippets(&ono_config.data_dirs);
+    for warning in &loaded.warnings {
+        eprintln!("Couldn't load {warning}");
+    }
+    let mut snippets = loaded.snippets;
+
+    if !args.dry_run {
+        fs::create_dir_all(data_dir)?;
+    }
+
+    let (mut added, mut skipped) = (0, 0);
+    for new_snippet in imported {
+        if let Some(reason) = get_skip_reason(&snippets, &new_snippet) {
+            eprintln!("Skipped `{}`: {reason}", new_snippet.title);
+            skipped += 1;
+            continue;
+        }
+
+        if args.dry_run {
+            println!("Would import `{}`", new_snippet.title);
+            snippets.push(Snippet {
+                title: new_snippet.title,
+                alias: new_snippet.alias,
+                ..Default::default()
+            });
+        } else {
+            let snippet = store::create_snippet(data_dir, &new_snippet)?;
+            println!("Imported `{}` to {}", snippet.title, snippet.path.display());
+            snippets.push(snippet);
+        }
+        added += 1;
+    }
+
+    let verb = if args.dry_run {
+        "Would import"
+    } else {
+        "Imported"
+    };
+    println!("{verb} {added} snippets, skipped {skipped}");
+    Ok(())
+}
+
+fn get_skip_reason(snippets: &[Snippet], new_snippet: &NewSnippet) -> Option<String> {
+    if new_snippet.title.trim().is_empty() {
+        return Some("it has no title".to_string());
+    }
+    if new_snippet.command.trim().is_empty() {
+        return Some("it has no command".to_string());
+    }
+
+    match store::find_duplicate(snippets, new_snippet)? {
+        Duplicate::Title(existing) if existing.path.as_os_str().is_empty() => {
+            Some("the title is already imported".to_string())
+        }
+        Duplicate::Title(existing) => Some(format!(
+            "the title is already used by {}",
+            existing.path.display()
+        )),
+        Duplicate::Alias(existing) => Some(format!(
+            "alias `{}` is already used by `{}`",
+            new_snippet.alias.as_deref().unwrap_or_default(),
+            existing.title
+        )),
+    }
+}
+
+/// Read every snippet from the file, or from every file of the format in the directory
+fn read_snippets(format: ImportFormat, path: &Path) -> Result<Vec<NewSnippet>> {
+    let files = if path.is_dir() {
+        find_files(path, format)?
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut result = vec![];
+    for file in files {
+        let content = fs::read_to_string(&file)
+            .map_err(|err| eyre!("can't read {}: {err}", file.display()))?;
+        let name = file
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let snippets = match format {
+            ImportFormat::Pet => pet::parse(&content),
+            ImportFormat::Navi => Ok(navi::parse(&content)),
+            ImportFormat::Cheat => Ok(cheat::parse(&content, &name)),
+            ImportFormat::Tldr => Ok(tldr::parse(&content)),
+            ImportFormat::Json => json::parse(&content),
+        };
+        result.extend(snippets.map_err(|err| eyre!("{}: {err}", file.display()))?);
+    }
+
+    Ok(result)
+}
+
+/// Files of the format in the directory and its subdirectories, hidden ones are skipped
+fn find_files(dir: &Path, format: ImportFormat) -> Result<Vec<PathBuf>> {
+    let mut result = vec![];
+    let mut dirs = vec![dir.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let is_hidden = path
+                .file_name()
+                .is_some_and(|n| n.to_string_lossy().starts_with('.'));
+            if is_hidden {
+                continue;
+            }
+
+            let extension = path.extension().map(|e| e.to_string_lossy().into_owned());
+            let is_match = match format {
+                ImportFormat::Navi => extension.as_deref() == Some("cheat"),
+                ImportFormat::Tldr => extension.as_deref() == Some("md"),
+                ImportFormat::Pet => extension.as_deref() == Some("toml"),
+                ImportFormat::Json => extension.as_deref() == Some("json"),
+                // cheat sheets have no extension
+                ImportFormat::Cheat => true,
+            };
+
+            if path.is_dir() {
+                dirs.push(path);
+            } else if is_match {
+                result.push(path);
+            }
+        }
+    }
+
+    result.sort();
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_reports_conflicts() {
+        let snippets = vec![Snippet {
+            title: "List directory".to_string(),
+            alias: Some("ll".to_string()),
+            path: PathBuf::from("/data/List directory.md"),
+            ..Default::default()
+        }];
+        let new_snippet = |title: &str, alias: Option<&str>, command: &str| NewSnippet {
+            title: title.to_string(),
+            alias: alias.map(String::from),
+            command: command.to_string(),
+            ..Default::default()
+        };
+
+        let test_cases = vec![
+            (new_snippet("Other", None, "ls"), None),
+            (
+                new_snippet("list directory", None, "ls"),
+                Some("the title is already used by /data/List directory.md"),
+            ),
+            (
+                new_snippet("Other", Some("ll"), "ls"),
+                Some("alias `ll` is already used by `List directory`"),
+            ),
+            (new_snippet("Other", None, " "), Some("it has no command")),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(
+                get_skip_reason(&snippets, &input).as_deref(),
+                expected,
+                "input: {input:?}"
+            );
+        }
+    }
+}
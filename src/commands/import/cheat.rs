@@ -0,0 +1,123 @@
+//! cheat sheets: plain text named after the command, with optional YAML frontmatter.
+//! Every `# comment` followed by command lines becomes a snippet tagged with the sheet name.
+
+use crate::store::{self, NewSnippet};
+
+pub fn parse(content: &str, sheet: &str) -> Vec<NewSnippet> {
+    let (tags, body) = split_frontmatter(content);
+    let mut tags = tags;
+    if !tags.iter().any(|t| t == sheet) {
+        tags.insert(0, sheet.to_string());
+    }
+
+    let mut result: Vec<NewSnippet> = vec![];
+    let mut comment: Vec<&str> = vec![];
+    let mut command: Vec<&str> = vec![];
+
+    let mut push_snippet = |comment: &mut Vec<&str>, command: &mut Vec<&str>| {
+        if !command.is_empty() {
+            let text = comment.join(" ");
+            let text = text.trim_end_matches(':').trim();
+            let title = if text.is_empty() { command[0] } else { text };
+            result.push(NewSnippet {
+                title: format!("{sheet}: {title}"),
+                tags: tags.clone(),
+                command: command.join("\n"),
+                ..Default::default()
+            });
+        }
+        comment.clear();
+        command.clear();
+    };
+
+    for line in body.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            push_snippet(&mut comment, &mut command);
+        } else if let Some(text) = trimmed.strip_prefix('#') {
+            if !command.is_empty() {
+                push_snippet(&mut comment, &mut command);
+            }
+            comment.push(text.trim());
+        } else {
+            command.push(line.trim_end());
+        }
+    }
+    push_snippet(&mut comment, &mut command);
+
+    result
+}
+
+/// Tags from the frontmatter, if there's one, and the rest of the sheet
+fn split_frontmatter(content: &str) -> (Vec<String>, &str) {
+    let Some(rest) = content.strip_prefix("---\n") else {
+        return (vec![], content);
+    };
+    let Some((frontmatter, body)) = rest.split_once("\n---") else {
+        return (vec![], content);
+    };
+
+    let tags = frontmatter
+        .lines()
+        .find_map(|line| line.strip_prefix("tags:"))
+        .map(|tags| store::parse_inline_list(tags.trim()))
+        .unwrap_or_default();
+    (tags, body.trim_start_matches('-'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_cheat_sheets() {
+        let content = "---
+syntax: bash
+tags: [ archive, compression ]
+---
+# To extract an uncompressed archive:
+tar -xvf /path/to/foo.tar
+
+# To create a gzipped archive
+# from a directory:
+tar -czvf foo.tgz dir
+tar -tzvf foo.tgz
+# no blank line before this one:
+tar -xzf foo.tgz
+";
+
+        let tags = vec![
+            "tar".to_string(),
+            "archive".to_string(),
+            "compression".to_string(),
+        ];
+        let snippet = |title: &str, command: &str| NewSnippet {
+            title: title.to_string(),
+            tags: tags.clone(),
+            command: command.to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            parse(content, "tar"),
+            vec![
+                snippet(
+                    "tar: To extract an uncompressed archive",
+                    "tar -xvf /path/to/foo.tar"
+                ),
+                snippet(
+                    "tar: To create a gzipped archive from a directory",
+                    "tar -czvf foo.tgz dir\ntar -tzvf foo.tgz"
+                ),
+                snippet("tar: no blank line before this one", "tar -xzf foo.tgz"),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_uses_command_as_title_without_comment() {
+        let snippets = parse("ls -la\n", "ls");
+        assert_eq!(snippets[0].title, "ls: ls -la");
+        assert_eq!(snippets[0].tags, vec!["ls".to_string()]);
+    }
+}
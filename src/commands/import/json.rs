@@ -0,0 +1,322 @@
+//! Snippets as a JSON array of objects, e.g. the output of `ono export --format json`.
+//! `{"snippets": [...]}` is accepted as well.
+
+use std::{iter::Peekable, str::Chars};
+
+use crate::{commands::import::ParseError, store::NewSnippet};
+
+#[derive(Debug, PartialEq)]
+enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+pub fn parse(content: &str) -> Result<Vec<NewSnippet>, ParseError> {
+    let value = parse_json(content)?;
+    let items = match value.get("snippets").unwrap_or(&value) {
+        JsonValue::Array(items) => items,
+        _ => return Err(ParseError::new(1, "expected an array of snippets")),
+    };
+
+    Ok(items.iter().map(to_new_snippet).collect())
+}
+
+fn to_new_snippet(item: &JsonValue) -> NewSnippet {
+    let get_str = |key| {
+        item.get(key)
+            .and_then(JsonValue::as_str)
+            .unwrap_or_default()
+    };
+    let tags = match item.get("tags") {
+        Some(JsonValue::Array(tags)) => tags
+            .iter()
+            .filter_map(JsonValue::as_str)
+            .map(String::from)
+            .collect(),
+        Some(JsonValue::String(tags)) => crate::store::parse_inline_list(tags),
+        _ => vec![],
+    };
+
+    NewSnippet {
+        title: get_str("title").to_string(),
+        description: get_str("description").to_string(),
+        alias: Some(get_str("alias"))
+            .filter(|a| !a.is_empty())
+            .map(String::from),
+        tags,
+        command: get_str("command").to_string(),
+    }
+}
+
+fn parse_json(content: &str) -> Result<JsonValue, ParseError> {
+    let mut parser = Parser {
+        chars: content.chars().peekable(),
+        line: 1,
+    };
+
+    let value = parser.parse_value()?;
+    parser.skip_whitespace();
+    match parser.chars.peek() {
+        Some(_) => Err(parser.error("unexpected characters after the value")),
+        None => Ok(value),
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> ParseError {
+        ParseError::new(self.line, message)
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        if c == Some('\n') {
+            self.line += 1;
+        }
+        c
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            _ => Err(self.error(&format!("expected `{expected}`"))),
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<JsonValue, ParseError> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some('{') => self.parse_object(),
+            Some('[') => self.parse_array(),
+            Some('"') => self.parse_string().map(JsonValue::String),
+            Some('t') => self.parse_literal("true", JsonValue::Bool(true)),
+            Some('f') => self.parse_literal("false", JsonValue::Bool(false)),
+            Some('n') => self.parse_literal("null", JsonValue::Null),
+            Some(c) if *c == '-' || c.is_ascii_digit() => self.parse_number(),
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<JsonValue, ParseError> {
+        self.expect('{')?;
+        let mut entries = vec![];
+
+        self.skip_whitespace();
+        if self.chars.peek() == Some(&'}') {
+            self.next();
+            return Ok(JsonValue::Object(entries));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.expect(':')?;
+            entries.push((key, self.parse_value()?));
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(JsonValue::Object(entries)),
+                _ => return Err(self.error("expected `,` or `}`")),
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<JsonValue, ParseError> {
+        self.expect('[')?;
+        let mut items = vec![];
+
+        self.skip_whitespace();
+        if self.chars.peek() == Some(&']') {
+            self.next();
+            return Ok(JsonValue::Array(items));
+        }
+
+        loop {
+            items.push(self.parse_value()?);
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(JsonValue::Array(items)),
+                _ => return Err(self.error("expected `,` or `]`")),
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, ParseError> {
+        if self.next() != Some('"') {
+            return Err(self.error("expected a string"));
+        }
+
+        let mut result = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(result),
+                Some('\\') => {
+                    let c = match self.next() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('u') => self.parse_unicode_escape()?,
+                        Some(c @ ('"' | '\\' | '/')) => c,
+                        _ => return Err(self.error("invalid escape sequence")),
+                    };
+                    result.push(c);
+                }
+                Some(c) => result.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    /// `\uXXXX`, characters outside the basic plane are written as surrogate pairs
+    fn parse_unicode_escape(&mut self) -> Result<char, ParseError> {
+        let high = self.parse_hex()?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| self.error("invalid unicode escape"));
+        }
+
+        if self.next() != Some('\\') || self.next() != Some('u') {
+            return Err(self.error("expected the low surrogate"));
+        }
+        let low = self.parse_hex()?;
+        char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF))
+            .ok_or_else(|| self.error("invalid unicode escape"))
+    }
+
+    fn parse_hex(&mut self) -> Result<u32, ParseError> {
+        let hex: String = (0..4).filter_map(|_| self.next()).collect();
+        u32::from_str_radix(&hex, 16).map_err(|_| self.error("invalid unicode escape"))
+    }
+
+    fn parse_literal(&mut self, literal: &str, value: JsonValue) -> Result<JsonValue, ParseError> {
+        for expected in literal.chars() {
+            if self.next() != Some(expected) {
+                return Err(self.error("expected a value"));
+            }
+        }
+        Ok(value)
+    }
+
+    fn parse_number(&mut self) -> Result<JsonValue, ParseError> {
+        let mut number = String::new();
+        while let Some(c) = self
+            .chars
+            .peek()
+            .filter(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+        {
+            number.push(*c);
+            self.next();
+        }
+
+        number
+            .parse()
+            .map(JsonValue::Number)
+            .map_err(|_| self.error("invalid number"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_json_values() {
+        let test_cases = vec![
+            ("null", JsonValue::Null),
+            (" true ", JsonValue::Bool(true)),
+            ("-1.5e2", JsonValue::Number(-150.0)),
+            (r#""a\"b\né😀""#, JsonValue::String("a\"b\né😀".to_string())),
+            (
+                r#"{"a": [1, {}], "b": []}"#,
+                JsonValue::Object(vec![
+                    (
+                        "a".to_string(),
+                        JsonValue::Array(vec![JsonValue::Number(1.0), JsonValue::Object(vec![])]),
+                    ),
+                    ("b".to_string(), JsonValue::Array(vec![])),
+                ]),
+            ),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(parse_json(input), Ok(expected), "input: {input}");
+        }
+    }
+
+    #[test]
+    fn it_reports_the_line_of_errors() {
+        let test_cases = vec![
+            ("[1,\n2,\n]", 3),
+            ("{\"a\" 1}", 1),
+            ("\"open", 1),
+            ("[] []", 1),
+        ];
+
+        for (input, line) in test_cases {
+            assert_eq!(parse_json(input).unwrap_err().line, line, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn it_parses_snippets() {
+        let content = r#"{"snippets": [
+            {"title": "Disk usage", "command": "df -h", "alias": "du", "tags": ["fs"], "used": 3},
+            {"title": "List", "description": "long", "command": "ls -l", "alias": null, "tags": "a, b"}
+        ]}"#;
+
+        assert_eq!(
+            parse(content).unwrap(),
+            vec![
+                NewSnippet {
+                    title: "Disk usage".to_string(),
+                    alias: Some("du".to_string()),
+                    tags: vec!["fs".to_string()],
+                    command: "df -h".to_string(),
+                    ..Default::default()
+                },
+                NewSnippet {
+                    title: "List".to_string(),
+                    description: "long".to_string(),
+                    tags: vec!["a".to_string(), "b".to_string()],
+                    command: "ls -l".to_string(),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+}
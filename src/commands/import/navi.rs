@@ -0,0 +1,94 @@
+//! navi cheat files: `% tags` apply to the snippets below them, `# description` starts a
+//! snippet and the lines after it are its command. `<var>` placeholders are kept as is.
+
+use crate::store::{self, NewSnippet};
+
+pub fn parse(content: &str) -> Vec<NewSnippet> {
+    let mut result: Vec<NewSnippet> = vec![];
+    let mut tags: Vec<String> = vec![];
+    // a snippet takes command lines until a blank line or another directive
+    let mut is_reading_command = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        let mut chars = trimmed.chars();
+        match chars.next() {
+            Some('%') => tags = store::parse_inline_list(chars.as_str()),
+            Some('#') => {
+                result.push(NewSnippet {
+                    title: chars.as_str().trim().to_string(),
+                    tags: tags.clone(),
+                    ..Default::default()
+                });
+                is_reading_command = true;
+                continue;
+            }
+            // variables, comments and extends have no ono equivalent
+            Some('$' | ';' | '@') => {}
+            Some(_) if is_reading_command => {
+                if let Some(snippet) = result.last_mut() {
+                    if !snippet.command.is_empty() {
+                        snippet.command.push('\n');
+                    }
+                    snippet.command.push_str(line.trim_end());
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        is_reading_command = false;
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_navi_cheats() {
+        let content = "% git, code
+
+# Change branch
+git checkout <branch>
+
+$ branch: git branch | awk '{print $NF}'
+
+; comment
+# Multi line
+git add -A \\
+  && git commit
+
+% docker
+# Prune
+docker system prune
+";
+
+        let tags = |t: &[&str]| t.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            parse(content),
+            vec![
+                NewSnippet {
+                    title: "Change branch".to_string(),
+                    tags: tags(&["git", "code"]),
+                    command: "git checkout <branch>".to_string(),
+                    ..Default::default()
+                },
+                NewSnippet {
+                    title: "Multi line".to_string(),
+                    tags: tags(&["git", "code"]),
+                    command: "git add -A \\\n  && git commit".to_string(),
+                    ..Default::default()
+                },
+                NewSnippet {
+                    title: "Prune".to_string(),
+                    tags: tags(&["docker"]),
+                    command: "docker system prune".to_string(),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+}
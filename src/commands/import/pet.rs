@@ -0,0 +1,200 @@
+//! pet snippet file, TOML with a `[[snippets]]` table per snippet.
+//! Only the part of TOML pet writes is supported: strings and arrays of strings.
+
+use crate::{commands::import::ParseError, store::NewSnippet};
+
+pub fn parse(content: &str) -> Result<Vec<NewSnippet>, ParseError> {
+    let mut result: Vec<NewSnippet> = vec![];
+    let mut lines = content.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+    while let Some((line_number, line)) = lines.next() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if line == "[[snippets]]" {
+            result.push(NewSnippet::default());
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(ParseError::new(line_number, "expected `key = value`"));
+        };
+        let Some(snippet) = result.last_mut() else {
+            return Err(ParseError::new(line_number, "expected `[[snippets]]`"));
+        };
+
+        let mut value = value.trim().to_string();
+        // multi-line strings continue until the closing quotes
+        for quotes in ["\"\"\"", "'''"] {
+            if value.starts_with(quotes) && (value.len() < 6 || !value.ends_with(quotes)) {
+                for (_, next_line) in lines.by_ref() {
+                    value.push('\n');
+                    value.push_str(next_line);
+                    if next_line.trim_end().ends_with(quotes) {
+                        break;
+                    }
+                }
+            }
+        }
+
+        let error = |message: &str| ParseError::new(line_number, message);
+        match key.trim() {
+            "description" => snippet.title = parse_string(&value).ok_or(error("invalid string"))?,
+            "command" => snippet.command = parse_string(&value).ok_or(error("invalid string"))?,
+            "tag" => snippet.tags = parse_array(&value).ok_or(error("invalid array"))?,
+            // output, folder and other keys have no ono equivalent
+            _ => {}
+        }
+    }
+
+    Ok(result)
+}
+
+fn parse_string(value: &str) -> Option<String> {
+    if let Some(inner) = value
+        .strip_prefix("\"\"\"")
+        .and_then(|v| v.strip_suffix("\"\"\""))
+    {
+        // a newline right after the opening quotes is trimmed
+        return unescape(inner.strip_prefix('\n').unwrap_or(inner));
+    }
+    if let Some(inner) = value
+        .strip_prefix("'''")
+        .and_then(|v| v.strip_suffix("'''"))
+    {
+        return Some(inner.strip_prefix('\n').unwrap_or(inner).to_string());
+    }
+    if let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        return unescape(inner);
+    }
+    value
+        .strip_prefix('\'')
+        .and_then(|v| v.strip_suffix('\''))
+        .map(String::from)
+}
+
+fn parse_array(value: &str) -> Option<Vec<String>> {
+    let inner = value.strip_prefix('[')?.strip_suffix(']')?;
+    let mut result = vec![];
+    let mut item = String::new();
+    let mut quote = None;
+
+    for c in inner.chars() {
+        match (quote, c) {
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                item.push(c);
+            }
+            (None, ',') => {
+                result.push(parse_string(item.trim())?);
+                item.clear();
+            }
+            (Some(q), _) if q == c && !item.ends_with('\\') => {
+                quote = None;
+                item.push(c);
+            }
+            _ => item.push(c),
+        }
+    }
+
+    if !item.trim().is_empty() {
+        result.push(parse_string(item.trim())?);
+    }
+    Some(result)
+}
+
+/// Escape sequences of basic strings
+fn unescape(value: &str) -> Option<String> {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        let escaped = match chars.next()? {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'u' => char::from_u32(
+                u32::from_str_radix(&chars.by_ref().take(4).collect::<String>(), 16).ok()?,
+            )?,
+            'U' => char::from_u32(
+                u32::from_str_radix(&chars.by_ref().take(8).collect::<String>(), 16).ok()?,
+            )?,
+            c @ ('"' | '\\') => c,
+            _ => return None,
+        };
+        result.push(escaped);
+    }
+
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_pet_snippets() {
+        let content = r#"
+[[snippets]]
+  description = "Ping google"
+  command = "ping <host=8.8.8.8>"
+  tag = ["network", 'google']
+  output = ""
+
+[[snippets]]
+  description = 'Show "quoted" path'
+  command = """
+echo \"$PATH\"
+ls"""
+  tag = []
+"#;
+
+        assert_eq!(
+            parse(content).unwrap(),
+            vec![
+                NewSnippet {
+                    title: "Ping google".to_string(),
+                    tags: vec!["network".to_string(), "google".to_string()],
+                    command: "ping <host=8.8.8.8>".to_string(),
+                    ..Default::default()
+                },
+                NewSnippet {
+                    title: "Show \"quoted\" path".to_string(),
+                    command: "echo \"$PATH\"\nls".to_string(),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_reports_invalid_lines() {
+        let test_cases = vec![
+            (
+                "description = \"a\"",
+                ParseError::new(1, "expected `[[snippets]]`"),
+            ),
+            (
+                "[[snippets]]\n\ncommand \"ls\"",
+                ParseError::new(3, "expected `key = value`"),
+            ),
+            (
+                "[[snippets]]\ncommand = \"ls",
+                ParseError::new(2, "invalid string"),
+            ),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(parse(input), Err(expected), "input: {input}");
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! tldr pages: `# name`, a `> summary` and `- description:` items each followed by a
+//! `` `command` ``. `{{placeholders}}` are converted to `<placeholders>`.
+
+use crate::store::NewSnippet;
+
+pub fn parse(content: &str) -> Vec<NewSnippet> {
+    let mut result: Vec<NewSnippet> = vec![];
+    let mut name = "";
+    let mut summary = "";
+    let mut item: Option<&str> = None;
+
+    for line in content.lines().map(str::trim) {
+        if let Some(value) = line.strip_prefix("# ") {
+            name = value.trim();
+        } else if let Some(value) = line.strip_prefix('>') {
+            if summary.is_empty() {
+                summary = value.trim();
+            }
+        } else if let Some(value) = line.strip_prefix("- ") {
+            item = Some(value.trim().trim_end_matches(':'));
+        } else if let Some(command) = line.strip_prefix('`').and_then(|l| l.strip_suffix('`'))
+            && let Some(description) = item.take()
+        {
+            result.push(NewSnippet {
+                title: format!("{name}: {description}"),
+                description: summary.to_string(),
+                tags: vec![name.to_string()],
+                command: convert_placeholders(command),
+                ..Default::default()
+            });
+        }
+    }
+
+    result
+}
+
+fn convert_placeholders(command: &str) -> String {
+    command.replace("{{", "<").replace("}}", ">")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_tldr_pages() {
+        let content = "# tar
+
+> Archiving utility.
+> More information: <https://www.gnu.org/software/tar>.
+
+- [c]reate an archive and write it to a [f]ile:
+
+`tar cf {{path/to/target.tar}} {{path/to/file1}}`
+
+- List the contents of a tar file:
+
+`tar tvf {{path/to/source.tar}}`
+";
+
+        let snippet = |title: &str, command: &str| NewSnippet {
+            title: title.to_string(),
+            description: "Archiving utility.".to_string(),
+            tags: vec!["tar".to_string()],
+            command: command.to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            parse(content),
+            vec![
+                snippet(
+                    "tar: [c]reate an archive and write it to a [f]ile",
+                    "tar cf <path/to/target.tar> <path/to/file1>"
+                ),
+                snippet(
+                    "tar: List the contents of a tar file",
+                    "tar tvf <path/to/source.tar>"
+                ),
+            ]
+        );
+    }
+}
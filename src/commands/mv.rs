@@ -0,0 +1,150 @@
+//! `ono mv`, renames a snippet and optionally moves it to another data directory
+
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use color_eyre::{
+    Result,
+    eyre::{bail, eyre},
+};
+
+use crate::{
+    cli::MvArgs,
+    config::{DataDir, OnoConfig},
+    store,
+};
+
+/// Rename the snippet with the alias or title, links to it in other notes are updated
+pub fn run(args: MvArgs, ono_config: &OnoConfig) -> Result<()> {
+    let loaded = store::load_snippets(&ono_config.data_dirs);
+    for warning in &loaded.warnings {
+        eprintln!("Couldn't load {warning}");
+    }
+
+    let mut snippets = loaded.snippets;
+    let snippet = store::find_snippet(&mut snippets, &args.name)
+        .ok_or_else(|| {
+            eyre!(
+                "there is no snippet with the alias or title `{}`",
+                args.name
+            )
+        })?
+        .clone();
+
+    let folder = match &args.folder {
+        Some(folder) => find_folder(&ono_config.data_dirs, folder, &snippet.path)
+            .ok_or_else(|| eyre!("there is no data directory or folder `{folder}`"))?,
+        None => snippet
+            .path
+            .parent()
+            .map(Path::to_path_buf)
+            .ok_or_else(|| eyre!("{} isn't in a folder", snippet.path.display()))?,
+    };
+
+    if let Some(existing) = store::find_title_clash(&snippets, &snippet, &args.title) {
+        bail!(
+            "title `{}` is already used by {}",
+            args.title.trim(),
+            existing.path.display()
+        );
+    }
+
+    let moved = store::move_snippet(&snippet, &ono_config.data_dirs, &folder, &args.title)?;
+    println!(
+        "Moved `{}` to {}",
+        snippet.title,
+        moved.snippet.path.display()
+    );
+    for note in &moved.updated_notes {
+        println!("Updated links in {}", note.display());
+    }
+    for note in &moved.stale_notes {
+        println!(
+            "Links in {} still point to the old data directory",
+            note.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Folder with the label of a data directory, optionally followed by folders inside it,
+/// e.g. `team/docker`, or with the path of a data directory or a folder inside one.
+/// Otherwise it's a folder inside the data directory the snippet is in, it doesn't have to exist.
+fn find_folder(data_dirs: &[DataDir], folder: &str, snippet_path: &Path) -> Option<PathBuf> {
+    let is_inside = |path: &Path| path.components().all(|c| matches!(c, Component::Normal(_)));
+
+    for data_dir in data_dirs {
+        let Some(label) = data_dir.label.as_deref() else {
+            continue;
+        };
+        let root = Path::new(&data_dir.path);
+        if folder == label {
+            return Some(root.to_path_buf());
+        }
+        if let Some(rest) = folder.strip_prefix(label).and_then(|f| f.strip_prefix('/'))
+            && is_inside(Path::new(rest))
+        {
+            return Some(root.join(rest));
+        }
+    }
+
+    // paths are compared canonicalized
+    if let Ok(canonical) = fs::canonicalize(folder) {
+        let found = data_dirs.iter().find_map(|d| {
+            let root = fs::canonicalize(&d.path).ok()?;
+            let relative = canonical.strip_prefix(root).ok()?;
+            Some(Path::new(&d.path).join(relative))
+        });
+        if found.is_some() {
+            return found;
+        }
+    }
+
+    let relative = Path::new(folder);
+    if !is_inside(relative) {
+        return None;
+    }
+    store::find_data_dir(data_dirs, snippet_path).map(|d| Path::new(&d.path).join(relative))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn it_finds_folders() {
+        let dir = TestDir::new("mv");
+        let team_dir = dir.join("team");
+        fs::create_dir_all(team_dir.join("docker")).unwrap();
+        let data_dirs = [(&*dir, None), (&*team_dir, Some("team"))].map(|(d, label)| DataDir {
+            path: d.to_string_lossy().into_owned(),
+            label: label.map(String::from),
+            read_only: false,
+        });
+        let snippet_path = dir.join("git/prune.md");
+
+        let test_cases = vec![
+            ("team", Some(team_dir.clone())),
+            ("team/docker", Some(team_dir.join("docker"))),
+            ("team/../docker", None),
+            (dir.to_str().unwrap(), Some(dir.to_path_buf())),
+            (team_dir.to_str().unwrap(), Some(team_dir.clone())),
+            ("cleanup", Some(dir.join("cleanup"))),
+            ("tools/cleanup", Some(dir.join("tools/cleanup"))),
+            ("../cleanup", None),
+            ("/", None),
+        ];
+
+        for (folder, expected) in test_cases {
+            assert_eq!(
+                find_folder(&data_dirs, folder, &snippet_path),
+                expected,
+                "folder: {folder}"
+            );
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! Questions asked on the terminal by the interactive commands
+
+use std::io::{self, BufRead, Write};
+
+/// Ask for a value, empty answer keeps the default
+pub fn prompt(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    question: &str,
+    default: Option<&str>,
+) -> io::Result<Option<String>> {
+    let answer = read_answer(input, output, question, default)?.unwrap_or_default();
+
+    if answer.is_empty() {
+        Ok(default.map(String::from))
+    } else {
+        Ok(Some(answer))
+    }
+}
+
+/// Ask the question and read the trimmed answer, nothing if the input has ended
+fn read_answer(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    question: &str,
+    default: Option<&str>,
+) -> io::Result<Option<String>> {
+    match default {
+        Some(d) => write!(output, "{question} [{d}]: ")?,
+        None => write!(output, "{question}: ")?,
+    }
+    output.flush()?;
+
+    let mut answer = String::new();
+    if input.read_line(&mut answer)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(answer.trim().to_string()))
+}
+
+/// Ask for a value, listing the suggestions above the question.
+/// `#<n>` picks the nth suggestion, empty answer keeps the default.
+/// The input ending, e.g. on Ctrl-D, is an `UnexpectedEof` error rather than the default.
+pub fn prompt_with_suggestions(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    question: &str,
+    default: Option<&str>,
+    suggestions: &[String],
+) -> io::Result<Option<String>> {
+    for (idx, suggestion) in suggestions.iter().enumerate() {
+        writeln!(output, "  #{} {suggestion}", idx + 1)?;
+    }
+
+    let answer = read_answer(input, output, question, default)?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "input ended"))?;
+    let answer = Some(answer)
+        .filter(|a| !a.is_empty())
+        .or_else(|| default.map(String::from));
+    let picked = answer
+        .as_deref()
+        .and_then(|a| a.strip_prefix('#'))
+        .and_then(|n| n.parse::<usize>().ok())
+        .and_then(|n| suggestions.get(n.checked_sub(1)?));
+
+    Ok(picked.cloned().or(answer))
+}
+
+/// Ask a yes or no question, empty answer is the default
+pub fn confirm(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    question: &str,
+    default: bool,
+) -> io::Result<bool> {
+    let hint = if default { "Y/n" } else { "y/N" };
+    let answer = prompt(input, output, &format!("{question} ({hint})"), None)?;
+
+    Ok(match answer.as_deref().map(str::to_lowercase).as_deref() {
+        Some("y" | "yes") => true,
+        Some("n" | "no") => false,
+        _ => default,
+    })
+}
+
+/// Ask to type the expected answer, anything else is a no
+pub fn confirm_typed(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    question: &str,
+    expected: &str,
+) -> io::Result<bool> {
+    let answer = prompt(
+        input,
+        output,
+        &format!("{question} Type `{expected}`"),
+        None,
+    )?;
+    Ok(answer.as_deref() == Some(expected))
+}
@@ -0,0 +1,232 @@
+//! `ono run`, executes a snippet through the user's shell
+
+use std::{
+    collections::HashMap,
+    env,
+    io::{self, BufRead, Write},
+    path::PathBuf,
+};
+
+use color_eyre::{
+    Result,
+    eyre::{bail, eyre},
+};
+
+use crate::{
+    commands::prompt::{confirm_typed, prompt_with_suggestions},
+    config::{OnoConfig, path_expander},
+    os_helper,
+    store::{
+        self, Snippet, danger, history,
+        placeholder::{self, Placeholder},
+        secret,
+    },
+};
+
+/// Number of values entered before offered as suggestions for a placeholder
+const SUGGESTION_COUNT: usize = 5;
+
+/// Run the snippet with the alias or title, returns its exit code
+pub fn run(name: &str, ono_config: &OnoConfig) -> Result<i32> {
+    let loaded = store::load_snippets(&ono_config.data_dirs);
+    for warning in &loaded.warnings {
+        eprintln!("Couldn't load {warning}");
+    }
+
+    let mut snippets = loaded.snippets;
+    let snippet = store::find_snippet(&mut snippets, name)
+        .ok_or_else(|| eyre!("there is no snippet with the alias or title `{name}`"))?;
+    run_snippet(snippet, &ono_config.danger_rules)
+}
+
+/// Ask for the placeholders, run the command in the snippet's `cwd` with its `env`
+/// and record the use. Placeholders default to the values entered last time,
+/// the values entered before are offered as suggestions.
+/// Dangerous commands are only run after typing `yes`, the rules are checked
+/// after substitution so placeholder values are checked too.
+pub fn run_snippet(snippet: &mut Snippet, danger_rules: &[danger::Rule]) -> Result<i32> {
+    let cwd = get_cwd(snippet)?;
+    let mut input = io::stdin().lock();
+    let mut output = io::stderr();
+
+    let history_path = history::get_history_path();
+    let records = history::load(&history_path);
+    let get_suggestions = |p: &Placeholder| {
+        let recent = |snippet| history::recent_values(&records, snippet, &p.name, SUGGESTION_COUNT);
+        let in_snippet = recent(Some(&*snippet));
+        let default = in_snippet
+            .first()
+            .map(|v| v.to_string())
+            .or_else(|| p.default.clone())
+            .or_else(|| recent(None).first().map(|v| v.to_string()));
+
+        // values entered in this snippet come first, then the ones from other snippets
+        let mut suggestions: Vec<String> = vec![];
+        for value in in_snippet.into_iter().chain(recent(None)) {
+            if suggestions.len() < SUGGESTION_COUNT && !suggestions.iter().any(|s| s == value) {
+                suggestions.push(value.to_string());
+            }
+        }
+        (default, suggestions)
+    };
+    let Some((command, variables)) =
+        fill_placeholders(&mut input, &mut output, &snippet.command, get_suggestions)?
+    else {
+        eprintln!("Cancelled, placeholders without a default need a value");
+        return Ok(1);
+    };
+
+    snippet.risks = danger::analyse(&command, danger_rules);
+    if snippet.is_dangerous() {
+        eprintln!("`{}` is dangerous:\n  {command}", snippet.title);
+        for risk in &snippet.risks {
+            eprintln!("- {risk}");
+        }
+        if !confirm_typed(&mut input, &mut output, "Run it anyway?", "yes")? {
+            eprintln!("Cancelled");
+            return Ok(1);
+        }
+    }
+    drop(input);
+
+    let status = os_helper::run_in_shell(&command, cwd.as_deref(), &snippet.env)?;
+    if let Err(e) = store::record_usage(snippet) {
+        log::warn!("Couldn't record usage of {}: {e}", snippet.path.display());
+    }
+
+    // killed by a signal has no code
+    let code = status.code().unwrap_or(1);
+    let record = history::Record {
+        exit_status: Some(code),
+        variables: variables
+            .into_iter()
+            .filter(|(name, _)| !secret::is_secret_name(name))
+            .collect(),
+        ..history::Record::new(snippet)
+    };
+    if let Err(e) = history::append(&history_path, &record) {
+        log::warn!("Couldn't record history: {e}");
+    }
+
+    Ok(code)
+}
+
+/// Working directory of the snippet, `~` and environment variables are expanded
+fn get_cwd(snippet: &Snippet) -> Result<Option<PathBuf>> {
+    let Some(cwd) = &snippet.cwd else {
+        return Ok(None);
+    };
+
+    let home_dir = dirs::home_dir();
+    let cwd =
+        path_expander::expand_path(cwd, |name| env::var(name).ok(), home_dir.as_deref(), None)
+            .map_err(|variable| {
+                eyre!(
+                    "cwd of `{}` uses `{variable}` which is not set",
+                    snippet.title
+                )
+            })?;
+    let cwd = PathBuf::from(cwd);
+    if !cwd.is_dir() {
+        bail!(
+            "cwd of `{}`, {}, is not a directory",
+            snippet.title,
+            cwd.display()
+        );
+    }
+
+    Ok(Some(cwd))
+}
+
+/// Command with the placeholders substituted, along with their values in order
+type FilledCommand = (String, Vec<(String, String)>);
+
+/// Ask for the value of each placeholder and substitute them.
+/// `get_suggestions` gives the default and the suggested values of a placeholder.
+/// Returns the command along with the placeholder values in order, or nothing when a
+/// placeholder without a default is left empty or the input ends, e.g. on Ctrl-D.
+fn fill_placeholders(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    command: &str,
+    get_suggestions: impl Fn(&Placeholder) -> (Option<String>, Vec<String>),
+) -> io::Result<Option<FilledCommand>> {
+    let mut values = vec![];
+    for p in placeholder::find_placeholders(command) {
+        let (default, suggestions) = get_suggestions(&p);
+        let answer =
+            prompt_with_suggestions(input, output, &p.name, default.as_deref(), &suggestions);
+        let value = match answer {
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            answer => answer?,
+        };
+        let Some(value) = value else {
+            return Ok(None);
+        };
+        values.push((p.name, value));
+    }
+
+    let map: HashMap<String, String> = values.iter().cloned().collect();
+    Ok(Some((placeholder::substitute(command, &map), values)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_fills_placeholders() {
+        let mut input = "example.com\n\n#2\n".as_bytes();
+        let mut output = vec![];
+        // the value entered last time takes precedence over the default
+        let get_suggestions = |p: &Placeholder| match p.name.as_str() {
+            "iface" => (
+                Some("eth0".to_string()),
+                vec!["eth0".to_string(), "wlan0".to_string()],
+            ),
+            _ => (p.default.clone(), vec![]),
+        };
+
+        let (command, values) = fill_placeholders(
+            &mut input,
+            &mut output,
+            "ping <host> -c <count=3> -I <iface=lo> && echo <host>",
+            get_suggestions,
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(
+            command,
+            "ping example.com -c 3 -I wlan0 && echo example.com"
+        );
+        assert_eq!(
+            values,
+            vec![
+                ("host".to_string(), "example.com".to_string()),
+                ("count".to_string(), "3".to_string()),
+                ("iface".to_string(), "wlan0".to_string()),
+            ]
+        );
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "host: count [3]:   #1 eth0\n  #2 wlan0\niface [eth0]: "
+        );
+    }
+
+    #[test]
+    fn it_cancels_on_empty_placeholders() {
+        // empty line, then end of input also for placeholders with a default
+        for input in ["\n", "", "/tmp\n"] {
+            let mut output = vec![];
+            let filled = fill_placeholders(
+                &mut input.as_bytes(),
+                &mut output,
+                "rm -r <dir>/*.<ext=log>",
+                |p: &Placeholder| (p.default.clone(), vec![]),
+            )
+            .unwrap();
+            assert_eq!(filled, None);
+        }
+    }
+}
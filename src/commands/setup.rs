@@ -0,0 +1,85 @@
+use std::{
+    env,
+    io::{self, Write},
+};
+
+use color_eyre::{Result, eyre::eyre};
+
+use crate::{
+    commands::prompt::{confirm, prompt},
+    config::{OnoConfig, path_expander, yaml_parser},
+    store,
+};
+
+/// Interactively set up the config file and the data directory
+pub fn run(ono_config: &OnoConfig) -> Result<()> {
+    let mut input = io::stdin().lock();
+    let mut output = io::stdout();
+
+    writeln!(
+        output,
+        "Setting up ono, press Enter to keep the value in brackets."
+    )?;
+
+    let data_dir = prompt(
+        &mut input,
+        &mut output,
+        "Data directory",
+        ono_config.data_dir.as_deref(),
+    )?;
+    let editor = prompt(
+        &mut input,
+        &mut output,
+        "Editor",
+        ono_config.editor.as_deref(),
+    )?;
+    let history_file = prompt(
+        &mut input,
+        &mut output,
+        "History file",
+        ono_config.history_file.as_deref(),
+    )?;
+
+    if let Some(data_dir) = &data_dir {
+        // expanded as the config does when reading it back, relative to the config file
+        let home_dir = dirs::home_dir();
+        let data_dir = path_expander::expand_path(
+            data_dir,
+            |name| env::var(name).ok(),
+            home_dir.as_deref(),
+            ono_config.path.parent(),
+        )
+        .map_err(|variable| eyre!("Data directory uses `{variable}` which is not set"))?;
+        std::fs::create_dir_all(&data_dir)?;
+        if confirm(&mut input, &mut output, "Add example snippets?", true)? {
+            store::write_example_snippets(&data_dir)?;
+        }
+    }
+
+    let path = &ono_config.path;
+    let question = format!("Write config to {}?", path.display());
+    if confirm(&mut input, &mut output, &question, true)? {
+        let values = [
+            ("data_dir", data_dir.as_deref()),
+            ("editor", editor.as_deref()),
+            ("history_file", history_file.as_deref()),
+        ];
+
+        if path.exists() {
+            // keep the user's comments and other keys
+            let mut content = std::fs::read_to_string(path)?;
+            for (key, value) in values {
+                if let Some(value) = value {
+                    content = yaml_parser::set_yaml_value(&content, key, value);
+                }
+            }
+            std::fs::write(path, content)?;
+        } else {
+            crate::config::create_config_file(path, &values)?;
+        }
+
+        writeln!(output, "Config written to {}", path.display())?;
+    }
+
+    Ok(())
+}
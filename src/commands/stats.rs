@@ -0,0 +1,147 @@
+//! `ono stats`, usage of the snippets from the local history
+
+use std::collections::HashMap;
+
+use chrono::{Days, Local, NaiveDate};
+use color_eyre::Result;
+
+use crate::{
+    config::OnoConfig,
+    store::{self, Snippet, history},
+};
+
+/// Number of snippets listed as top snippets
+const TOP_COUNT: usize = 10;
+/// Number of days shown in the activity
+const ACTIVITY_DAYS: u64 = 14;
+/// Width of the longest activity bar
+const BAR_WIDTH: usize = 30;
+
+pub fn run(ono_config: &OnoConfig) -> Result<()> {
+    let loaded = store::load_snippets(&ono_config.data_dirs);
+    for warning in &loaded.warnings {
+        eprintln!("Couldn't load {warning}");
+    }
+
+    let history = history::load(&history::get_history_path());
+    print!(
+        "{}",
+        format_stats(&loaded.snippets, &history, Local::now().date_naive())
+    );
+    Ok(())
+}
+
+fn format_stats(snippets: &[Snippet], history: &[history::Record], today: NaiveDate) -> String {
+    let mut content = String::new();
+
+    // snippets removed since they're used are shown with the file name they're used with
+    let mut counts: HashMap<String, (String, usize)> = HashMap::new();
+    for record in history {
+        let title = snippets
+            .iter()
+            .find(|s| record.is_for(s))
+            .map(|s| s.title.clone())
+            .unwrap_or_else(|| format!("{} (removed)", record.path.display()));
+        counts.entry(record.id.clone()).or_insert((title, 0)).1 += 1;
+    }
+    let mut top: Vec<&(String, usize)> = counts.values().collect();
+    top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    content.push_str("Top snippets\n");
+    if top.is_empty() {
+        content.push_str("  No uses recorded yet\n");
+    }
+    let width = top.first().map_or(0, |(_, count)| count.to_string().len());
+    for (title, count) in top.iter().take(TOP_COUNT) {
+        content.push_str(&format!("  {count:>width$}  {title}\n"));
+    }
+
+    content.push_str(&format!("\nActivity, last {ACTIVITY_DAYS} days\n"));
+    let days: Vec<(NaiveDate, usize)> = (0..ACTIVITY_DAYS)
+        .rev()
+        .filter_map(|offset| today.checked_sub_days(Days::new(offset)))
+        .map(|day| {
+            let count = history
+                .iter()
+                .filter(|r| r.time.date_naive() == day)
+                .count();
+            (day, count)
+        })
+        .collect();
+    let max = days
+        .iter()
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let width = max.to_string().len();
+    for (day, count) in days {
+        let bar = "█".repeat((count * BAR_WIDTH).div_ceil(max));
+        content.push_str(format!("  {day}  {count:>width$} {bar}").trim_end());
+        content.push('\n');
+    }
+
+    let never_used: Vec<&Snippet> = snippets
+        .iter()
+        .filter(|s| s.used == 0 && !counts.contains_key(&s.id))
+        .collect();
+    content.push_str(&format!("\nNever used ({})\n", never_used.len()));
+    for snippet in never_used {
+        content.push_str(&format!("  {}\n", snippet.title));
+    }
+
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::path::PathBuf;
+
+    #[test]
+    fn it_formats_stats() {
+        let snippet = |title: &str, used: u32| Snippet {
+            id: title.to_string(),
+            title: title.to_string(),
+            path: PathBuf::from(format!("/data/{title}.md")),
+            used,
+            ..Default::default()
+        };
+        let snippets = vec![
+            snippet("list", 0),
+            snippet("disk", 0),
+            snippet("synced", 3),
+            snippet("unused", 0),
+        ];
+        let record = |title: &str, day: u32| history::Record {
+            time: Local.with_ymd_and_hms(2026, 3, day, 12, 0, 0).unwrap(),
+            id: title.to_string(),
+            path: PathBuf::from(format!("/data/{title}.md")),
+            cwd: None,
+            git_root: None,
+            exit_status: Some(0),
+            variables: vec![],
+        };
+        let history = vec![
+            record("disk", 13),
+            record("list", 14),
+            record("list", 14),
+            record("gone", 10),
+        ];
+
+        let stats = format_stats(
+            &snippets,
+            &history,
+            NaiveDate::from_ymd_opt(2026, 3, 14).unwrap(),
+        );
+
+        assert!(
+            stats.starts_with("Top snippets\n  2  list\n  1  /data/gone.md (removed)\n  1  disk\n")
+        );
+        assert!(stats.contains("  2026-03-01  0\n"));
+        assert!(stats.contains(&format!("  2026-03-13  1 {}\n", "█".repeat(15))));
+        assert!(stats.contains(&format!("  2026-03-14  2 {}\n", "█".repeat(30))));
+        assert!(stats.ends_with("\nNever used (1)\n  unused\n"));
+    }
+}
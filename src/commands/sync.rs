@@ -0,0 +1,531 @@
+//! `ono sync`, commits the changes in the data directory, pulls with rebase and pushes.
+//! Conflicting usage counters are merged, other conflicts are left to the user.
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use color_eyre::{
+    Result,
+    eyre::{WrapErr, bail, eyre},
+};
+
+use crate::{config::OnoConfig, store};
+
+/// What the sync did, for reporting
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    /// Message of the commit made for the local changes
+    pub commit: Option<String>,
+    /// Remote synced with, `None` if there's no remote
+    pub remote: Option<String>,
+    /// Files whose usage counters are merged after a conflict
+    pub merged: Vec<String>,
+}
+
+pub fn run(ono_config: &OnoConfig) -> Result<()> {
+    let data_dir = ono_config
+        .writable_data_dir()
+        .ok_or_else(|| eyre!("there is no writable data directory"))?;
+
+    let report = sync(Path::new(data_dir))?;
+    match &report.commit {
+        Some(message) => println!("Committed: {}", message.lines().next().unwrap_or_default()),
+        None => println!("No local changes to commit"),
+    }
+    for path in &report.merged {
+        println!("Merged usage counters of {path}");
+    }
+    match &report.remote {
+        Some(remote) => println!("Synced with {remote}"),
+        None => println!("There is no remote to sync with, add one with `git remote add`"),
+    }
+
+    Ok(())
+}
+
+pub fn sync(data_dir: &Path) -> Result<SyncReport> {
+    let repo = Repo::open(data_dir)?;
+    let mut report = SyncReport {
+        commit: repo.commit_changes()?,
+        ..Default::default()
+    };
+
+    let Some((remote, branch)) = repo.get_remote()? else {
+        return Ok(report);
+    };
+
+    report.merged = repo.pull(&remote, &branch)?;
+    repo.git(&["push", "-q", "-u", &remote, &format!("HEAD:{branch}")])?;
+    report.remote = Some(remote);
+    Ok(report)
+}
+
+/// Change of a file in a commit
+#[derive(Debug, PartialEq)]
+enum Change {
+    Added(String),
+    Updated(String),
+    /// Only `used` and `last_used` changed
+    UsageRecorded(String),
+    Removed(String),
+    Renamed(String, String),
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Added(name) => write!(f, "add {name}"),
+            Change::Updated(name) => write!(f, "update {name}"),
+            Change::UsageRecorded(name) => write!(f, "record usage of {name}"),
+            Change::Removed(name) => write!(f, "remove {name}"),
+            Change::Renamed(old, new) => write!(f, "rename {old} to {new}"),
+        }
+    }
+}
+
+/// Git repository the data directory is in, commands run in the data directory
+struct Repo {
+    dir: PathBuf,
+    root: PathBuf,
+}
+
+impl Repo {
+    fn open(dir: &Path) -> Result<Self> {
+        let mut repo = Repo {
+            dir: dir.to_path_buf(),
+            root: dir.to_path_buf(),
+        };
+
+        match repo.git(&["rev-parse", "--show-toplevel"]) {
+            Ok(root) => repo.root = PathBuf::from(root.trim()),
+            Err(_) => bail!(
+                "{} isn't a git repository, run `git init` in it to sync it",
+                dir.display()
+            ),
+        }
+        Ok(repo)
+    }
+
+    /// Run git in the data directory, returns stdout
+    fn git(&self, args: &[&str]) -> Result<String> {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(&self.dir)
+            // rebase --continue shouldn't open an editor
+            .env("GIT_EDITOR", "true")
+            .output()
+            .wrap_err("failed to run git")?;
+
+        if !output.status.success() {
+            bail!(
+                "`git {}` failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Commit every change in the data directory, returns the commit message
+    fn commit_changes(&self) -> Result<Option<String>> {
+        self.git(&["add", "-A", "--", "."])?;
+        let name_status = self.git(&["diff", "--cached", "--name-status", "-z", "--relative"])?;
+
+        let is_usage_only = |path: &str| {
+            self.git(&["diff", "--cached", "-U0", "--relative", "--", path])
+                .is_ok_and(|diff| is_usage_only(&diff))
+        };
+        // removed files are read from the last commit, added ones from the index
+        let get_id = |path: &str, removed: bool| {
+            let rev = if removed { "HEAD" } else { "" };
+            self.git(&["show", &format!("{rev}:./{path}")])
+                .ok()
+                .and_then(|content| store::read_id(&content))
+        };
+        let changes = parse_changes(&name_status, is_usage_only, get_id);
+        let Some(message) = get_commit_message(&changes) else {
+            return Ok(None);
+        };
+
+        self.git(&["commit", "-q", "-m", &message, "--", "."])?;
+        Ok(Some(message))
+    }
+
+    /// Remote and branch to sync with, the upstream if there's one, otherwise the branch
+    /// with the same name on `origin` or the only remote
+    fn get_remote(&self) -> Result<Option<(String, String)>> {
+        let branch = self.git(&["symbolic-ref", "--short", "HEAD"])?;
+        let branch = branch.trim();
+
+        let config = |key: &str| {
+            self.git(&["config", &format!("branch.{branch}.{key}")])
+                .ok()
+                .map(|v| v.trim().to_string())
+        };
+        if let (Some(remote), Some(merge)) = (config("remote"), config("merge")) {
+            let remote_branch = merge.trim_start_matches("refs/heads/").to_string();
+            return Ok(Some((remote, remote_branch)));
+        }
+
+        let remotes = self.git(&["remote"])?;
+        let remotes: Vec<&str> = remotes.lines().collect();
+        let remote = match remotes.as_slice() {
+            [] => return Ok(None),
+            _ if remotes.contains(&"origin") => "origin",
+            [remote, ..] => remote,
+        };
+        Ok(Some((remote.to_string(), branch.to_string())))
+    }
+
+    /// Pull with rebase, merging conflicting usage counters.
+    /// Returns the merged files, the rebase is aborted on other conflicts or failures.
+    fn pull(&self, remote: &str, branch: &str) -> Result<Vec<String>> {
+        let remote_heads = self.git(&["ls-remote", "--heads", remote, branch])?;
+        if remote_heads.trim().is_empty() {
+            // nothing is pushed yet
+            return Ok(vec![]);
+        }
+
+        let mut merged = vec![];
+        let mut result = self.git(&["pull", "-q", "--rebase", "--autostash", remote, branch]);
+        while let Err(err) = result {
+            if !self.is_rebasing()? {
+                return Err(err);
+            }
+
+            // the rebase stopped for another reason, e.g. a failing hook, continuing won't help
+            let conflicts = self.git(&["diff", "--name-only", "--diff-filter=U", "-z"])?;
+            let conflicts: Vec<&str> = conflicts.split('\0').filter(|p| !p.is_empty()).collect();
+            if conflicts.is_empty() {
+                self.git(&["rebase", "--abort"])?;
+                return Err(err.wrap_err("pull stopped without conflicts, the rebase is aborted"));
+            }
+
+            for path in conflicts {
+                // stage 2 is the upstream version and stage 3 the local one while rebasing
+                let upstream = self.git(&["show", &format!(":2:{path}")]);
+                let local = self.git(&["show", &format!(":3:{path}")]);
+                let content = match (upstream, local) {
+                    (Ok(upstream), Ok(local)) => {
+                        store::merge_usage(Path::new(path), &upstream, &local)
+                    }
+                    _ => None,
+                };
+
+                let Some(content) = content else {
+                    self.git(&["rebase", "--abort"])?;
+                    bail!(
+                        "{path} is changed both locally and on {remote}, pull and resolve the conflict with git in {}",
+                        self.root.display()
+                    );
+                };
+
+                fs::write(self.root.join(path), content)?;
+                self.git(&["add", "--", &self.root.join(path).to_string_lossy()])?;
+                merged.push(path.to_string());
+            }
+
+            result = self.git(&["rebase", "--continue"]);
+        }
+
+        Ok(merged)
+    }
+
+    fn is_rebasing(&self) -> Result<bool> {
+        for name in ["rebase-merge", "rebase-apply"] {
+            let path = self.git(&["rev-parse", "--git-path", name])?;
+            if self.dir.join(path.trim()).exists() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// Parse `git diff --name-status -z`. An added and a removed snippet with the same id
+/// are a rename, git only detects renames of files that are mostly unchanged.
+fn parse_changes(
+    name_status: &str,
+    is_usage_only: impl Fn(&str) -> bool,
+    get_id: impl Fn(&str, bool) -> Option<String>,
+) -> Vec<Change> {
+    let mut tokens = name_status.split('\0').filter(|t| !t.is_empty());
+    let mut changes = vec![];
+    let mut added_ids: Vec<(usize, String)> = vec![];
+    let mut removed_ids: Vec<(usize, String)> = vec![];
+
+    while let Some(status) = tokens.next() {
+        let Some(path) = tokens.next() else {
+            break;
+        };
+
+        let status = status.chars().next();
+        let id = match status {
+            Some('A') => get_id(path, false).map(|id| (&mut added_ids, id)),
+            Some('D') => get_id(path, true).map(|id| (&mut removed_ids, id)),
+            _ => None,
+        };
+        if let Some((ids, id)) = id {
+            ids.push((changes.len(), id));
+        }
+
+        let change = match status {
+            Some('A') => Change::Added(describe(path)),
+            Some('D') => Change::Removed(describe(path)),
+            Some('M') if is_usage_only(path) => Change::UsageRecorded(describe(path)),
+            Some('R') => match tokens.next() {
+                Some(new_path) => Change::Renamed(describe(path), describe(new_path)),
+                None => break,
+            },
+            _ => Change::Updated(describe(path)),
+        };
+        changes.push(change);
+    }
+
+    let mut renamed = vec![];
+    for (added_idx, id) in added_ids {
+        let Some(&(removed_idx, _)) = removed_ids.iter().find(|(_, r)| *r == id) else {
+            continue;
+        };
+        if let (Change::Removed(old), Change::Added(new)) =
+            (&changes[removed_idx], &changes[added_idx])
+        {
+            changes[added_idx] = Change::Renamed(old.clone(), new.clone());
+            renamed.push(removed_idx);
+        }
+    }
+
+    changes
+        .into_iter()
+        .enumerate()
+        .filter(|(idx, _)| !renamed.contains(idx))
+        .map(|(_, change)| change)
+        .collect()
+}
+
+/// Snippets are described by their name, other files by their path
+fn describe(path: &str) -> String {
+    let path_buf = Path::new(path);
+    match path_buf.extension().and_then(|e| e.to_str()) {
+        Some("md") => format!(
+            "snippet {}",
+            path_buf.file_stem().unwrap_or_default().to_string_lossy()
+        ),
+        _ => path.to_string(),
+    }
+}
+
+/// The change itself for a single change, otherwise a summary with a line per change
+fn get_commit_message(changes: &[Change]) -> Option<String> {
+    match changes {
+        [] => None,
+        [change] => Some(change.to_string()),
+        _ => {
+            let lines: Vec<String> = changes.iter().map(|c| format!("- {c}")).collect();
+            Some(format!(
+                "update {} files\n\n{}",
+                changes.len(),
+                lines.join("\n")
+            ))
+        }
+    }
+}
+
+/// Whether every changed line of the diff is a usage counter or a frontmatter delimiter
+fn is_usage_only(diff: &str) -> bool {
+    let mut changed_lines = diff
+        .lines()
+        .filter(|l| !l.starts_with("+++ ") && !l.starts_with("--- "))
+        .filter_map(|l| l.strip_prefix('+').or_else(|| l.strip_prefix('-')))
+        .peekable();
+
+    changed_lines.peek().is_some()
+        && changed_lines.all(|l| {
+            l.starts_with("used:")
+                || l.starts_with("last_used:")
+                || l.trim().is_empty()
+                || l == "---"
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn it_describes_changes() {
+        let name_status = "A\0docker-prune.md\0M\0ls.md\0M\0df.md\0D\0old.md\0R087\0a.md\0b.md\0";
+        let changes = parse_changes(name_status, |path| path == "ls.md", |_, _| None);
+
+        assert_eq!(
+            changes,
+            vec![
+                Change::Added("snippet docker-prune".to_string()),
+                Change::UsageRecorded("snippet ls".to_string()),
+                Change::Updated("snippet df".to_string()),
+                Change::Removed("snippet old".to_string()),
+                Change::Renamed("snippet a".to_string(), "snippet b".to_string()),
+            ]
+        );
+        assert_eq!(
+            get_commit_message(&changes[..1]).as_deref(),
+            Some("add snippet docker-prune")
+        );
+        assert_eq!(
+            get_commit_message(&changes[1..3]).as_deref(),
+            Some("update 2 files\n\n- record usage of snippet ls\n- update snippet df")
+        );
+        assert_eq!(get_commit_message(&[]), None);
+    }
+
+    #[test]
+    fn it_detects_renames_by_id() {
+        let name_status = "D\0ls.md\0A\0list files.md\0D\0old.md\0A\0new.md\0";
+        let get_id = |path: &str, removed: bool| match (path, removed) {
+            ("ls.md", true) | ("list files.md", false) => Some("abc".to_string()),
+            ("old.md", true) => Some("old".to_string()),
+            ("new.md", false) => Some("new".to_string()),
+            _ => None,
+        };
+        let changes = parse_changes(name_status, |_| false, get_id);
+
+        assert_eq!(
+            changes,
+            vec![
+                Change::Renamed("snippet ls".to_string(), "snippet list files".to_string()),
+                Change::Removed("snippet old".to_string()),
+                Change::Added("snippet new".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_detects_usage_only_diffs() {
+        let usage = "--- a/ls.md\n+++ b/ls.md\n@@ -2 +2,2 @@\n-used: 1\n+used: 2\n+last_used: 2026-01-01T00:00:00+00:00\n";
+        let command = "--- a/ls.md\n+++ b/ls.md\n@@ -2 +2 @@\n-used: 1\n+used: 2\n@@ -6 +6 @@\n-ls\n+ls -la\n";
+
+        assert!(is_usage_only(usage));
+        assert!(!is_usage_only(command));
+        assert!(!is_usage_only(""));
+    }
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(status.status.success(), "git {args:?}: {status:?}");
+    }
+
+    /// Bare remote with two clones, as on two machines. They're removed with the directory.
+    fn setup_repos(name: &str) -> (TestDir, PathBuf, PathBuf) {
+        let dir = TestDir::new(&format!("sync-{name}"));
+
+        git(&dir, &["init", "-q", "--bare", "remote.git"]);
+        let clones = ["a", "b"].map(|clone| {
+            git(&dir, &["clone", "-q", "remote.git", clone]);
+            let clone_dir = dir.join(clone);
+            git(&clone_dir, &["config", "user.name", "ono"]);
+            git(&clone_dir, &["config", "user.email", "ono@example.com"]);
+            git(&clone_dir, &["config", "commit.gpgsign", "false"]);
+            clone_dir
+        });
+
+        let [a, b] = clones;
+        (dir, a, b)
+    }
+
+    #[test]
+    fn it_syncs_through_a_remote() {
+        let (_dir, a, b) = setup_repos("remote");
+
+        fs::write(a.join("docker-prune.md"), "```\ndocker system prune\n```\n").unwrap();
+        let report = sync(&a).unwrap();
+        assert_eq!(report.commit.as_deref(), Some("add snippet docker-prune"));
+        assert_eq!(report.remote.as_deref(), Some("origin"));
+
+        let report = sync(&b).unwrap();
+        assert_eq!(report.commit, None);
+        assert!(b.join("docker-prune.md").exists());
+    }
+
+    #[test]
+    fn it_merges_conflicting_usage_counters() {
+        let (_dir, a, b) = setup_repos("conflict");
+        let snippet = |used: u32, last_used: &str| {
+            format!("---\nused: {used}\nlast_used: {last_used}\n---\n\n```\nls\n```\n")
+        };
+
+        fs::write(a.join("ls.md"), snippet(1, "2026-01-01T00:00:00+00:00")).unwrap();
+        sync(&a).unwrap();
+        sync(&b).unwrap();
+
+        fs::write(a.join("ls.md"), snippet(7, "2026-01-02T00:00:00+00:00")).unwrap();
+        fs::write(b.join("ls.md"), snippet(2, "2026-01-03T00:00:00+00:00")).unwrap();
+        let report = sync(&a).unwrap();
+        assert_eq!(report.commit.as_deref(), Some("record usage of snippet ls"));
+
+        let report = sync(&b).unwrap();
+        assert_eq!(report.merged, vec!["ls.md".to_string()]);
+        let content = fs::read_to_string(b.join("ls.md")).unwrap();
+        assert!(content.contains("used: 7"));
+
+        sync(&a).unwrap();
+        assert_eq!(fs::read_to_string(a.join("ls.md")).unwrap(), content);
+    }
+
+    #[test]
+    fn it_aborts_on_other_conflicts() {
+        let (_dir, a, b) = setup_repos("abort");
+
+        fs::write(a.join("ls.md"), "```\nls\n```\n").unwrap();
+        sync(&a).unwrap();
+        sync(&b).unwrap();
+
+        fs::write(a.join("ls.md"), "```\nls -a\n```\n").unwrap();
+        fs::write(b.join("ls.md"), "```\nls -l\n```\n").unwrap();
+        sync(&a).unwrap();
+
+        assert!(sync(&b).is_err());
+        let repo = Repo::open(&b).unwrap();
+        assert!(!repo.is_rebasing().unwrap());
+        assert_eq!(
+            fs::read_to_string(b.join("ls.md")).unwrap(),
+            "```\nls -l\n```\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_aborts_when_the_rebase_fails_without_conflicts() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (_dir, a, b) = setup_repos("hook");
+        let snippet = |used: u32, last_used: &str| {
+            format!("---\nused: {used}\nlast_used: {last_used}\n---\n\n```\nls\n```\n")
+        };
+
+        fs::write(a.join("ls.md"), snippet(1, "2026-01-01T00:00:00+00:00")).unwrap();
+        sync(&a).unwrap();
+        sync(&b).unwrap();
+
+        fs::write(a.join("ls.md"), snippet(7, "2026-01-02T00:00:00+00:00")).unwrap();
+        sync(&a).unwrap();
+        fs::write(b.join("ls.md"), snippet(2, "2026-01-03T00:00:00+00:00")).unwrap();
+        git(&b, &["commit", "-q", "-am", "record usage"]);
+
+        // the merged counters can't be committed
+        let hook = b.join(".git/hooks/prepare-commit-msg");
+        fs::write(&hook, "#!/bin/sh\nexit 1\n").unwrap();
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert!(sync(&b).is_err());
+        let repo = Repo::open(&b).unwrap();
+        assert!(!repo.is_rebasing().unwrap());
+    }
+}
@@ -1,46 +1,372 @@
 use std::{
     collections::HashMap,
+    env, fmt,
     fs::{self},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
-use crate::{config::yaml_parser::YamlValue, os_helper};
+use crate::{config::yaml_parser::YamlValue, os_helper, store::danger::Rule};
 
+pub mod path_expander;
 pub mod yaml_parser;
 
+/// Keys that can be set in the config file
+pub static CONFIG_KEYS: [&str; 3] = ["data_dir", "editor", "history_file"];
+
+/// Comments written above the keys when the config file is created
+static CONFIG_KEY_COMMENTS: [(&str, &str); 3] = [
+    ("data_dir", "Location of command snippets"),
+    ("editor", "Your default text editor"),
+    ("history_file", "Location of your shell history file"),
+];
+
 #[derive(Debug)]
 pub struct OnoConfig {
     pub data_dir: Option<String>,
     pub editor: Option<String>,
     pub history_file: Option<String>,
+    /// Directories snippets are loaded from, in priority order
+    pub data_dirs: Vec<DataDir>,
+    /// Danger rules checked along with the default ones
+    pub danger_rules: Vec<Rule>,
+    /// Config file the values are read from, it may not exist
+    pub path: PathBuf,
+    sources: HashMap<String, ConfigSource>,
+}
+
+/// A directory snippets are loaded from
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataDir {
+    pub path: String,
+    /// Shown next to the snippets from this directory
+    pub label: Option<String>,
+    /// Snippet files are never written, usage is kept in a local sidecar file instead
+    pub read_only: bool,
+}
+
+/// Config values given as command line flags, they take precedence over everything else
+#[derive(Debug, Default, PartialEq)]
+pub struct ConfigOverrides {
+    pub config_file: Option<String>,
+    pub data_dir: Option<String>,
+}
+
+/// Where the effective value of a config key comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigSource {
+    Flag,
+    Env,
+    File,
+    Default,
 }
 
 #[derive(Debug, PartialEq)]
 pub enum OnoConfigError {
     FileMalformed,
+    FileWriteError,
+    UnknownKey(String),
+    UnresolvedVariable { key: String, variable: String },
 }
 
 static CONFIG_FOLDER: &str = "ono";
 static CONFIG_FILE: &str = "ono.yaml";
+static CONFIG_ENV: &str = "ONO_CONFIG";
+
+impl OnoConfig {
+    /// Get the effective value of a config key
+    pub fn get(&self, key: &str) -> Option<&str> {
+        match key {
+            "data_dir" => self.data_dir.as_deref(),
+            "editor" => self.editor.as_deref(),
+            "history_file" => self.history_file.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// New snippets are written to the first writable data directory
+    pub fn writable_data_dir(&self) -> Option<&str> {
+        self.data_dirs
+            .iter()
+            .find(|d| !d.read_only)
+            .map(|d| d.path.as_str())
+    }
+
+    /// Get where the value of a config key comes from
+    pub fn source(&self, key: &str) -> Option<ConfigSource> {
+        self.sources.get(key).copied()
+    }
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Flag => write!(f, "flag"),
+            ConfigSource::Env => write!(f, "env"),
+            ConfigSource::File => write!(f, "file"),
+            ConfigSource::Default => write!(f, "default"),
+        }
+    }
+}
+
+impl fmt::Display for DataDir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let options: Vec<&str> = self
+            .label
+            .as_deref()
+            .into_iter()
+            .chain(self.read_only.then_some("readonly"))
+            .collect();
+
+        if options.is_empty() {
+            write!(f, "{}", self.path)
+        } else {
+            write!(f, "{} [{}]", self.path, options.join(", "))
+        }
+    }
+}
 
-pub fn get_config() -> Result<OnoConfig, OnoConfigError> {
-    // try to read the config from file
-    let path = get_full_config_path(true);
-    let config_map = yaml_parser::parse_yaml_from_file(&path).unwrap_or(get_default_config());
-    let get_val = |key: &str| {
-        config_map
-            .get(key)
-            .and_then(|v| v.as_str())
-            .map(String::from)
+impl fmt::Display for OnoConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OnoConfigError::FileMalformed => write!(f, "config file is malformed"),
+            OnoConfigError::FileWriteError => write!(f, "could not write the config file"),
+            OnoConfigError::UnknownKey(key) => write!(
+                f,
+                "unknown config key `{key}`, valid keys are: {}",
+                CONFIG_KEYS.join(", ")
+            ),
+            OnoConfigError::UnresolvedVariable { key, variable } => write!(
+                f,
+                "config key `{key}` uses environment variable `{variable}` which is not set"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for OnoConfigError {}
+
+pub fn get_config(overrides: &ConfigOverrides) -> Result<OnoConfig, OnoConfigError> {
+    // try to read the config from file, missing file means every key falls back
+    let path = get_config_path(overrides);
+    let file_map = match yaml_parser::parse_yaml_from_file(&path) {
+        Ok(map) => map,
+        Err(yaml_parser::YamlParserError::FileNotFound) => HashMap::new(),
+        // a file with only comments, e.g. created by `ono config edit`, is not malformed
+        Err(yaml_parser::YamlParserError::FileParseError) if is_blank_file(&path) => HashMap::new(),
+        Err(yaml_parser::YamlParserError::FileParseError) => {
+            return Err(OnoConfigError::FileMalformed);
+        }
     };
 
+    resolve_config(
+        path,
+        &file_map,
+        &get_default_config(),
+        |name| env::var(name).ok(),
+        overrides,
+    )
+}
+
+fn is_blank_file(path: &Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|content| {
+        content
+            .lines()
+            .map(str::trim)
+            .all(|l| l.is_empty() || l.starts_with('#'))
+    })
+}
+
+/// Resolve every config key by precedence: flag, environment variable, config file, default.
+/// Path values are expanded, relative paths in the config file are relative to the file.
+fn resolve_config(
+    path: PathBuf,
+    file_map: &HashMap<String, YamlValue>,
+    default_map: &HashMap<String, YamlValue>,
+    get_env: impl Fn(&str) -> Option<String>,
+    overrides: &ConfigOverrides,
+) -> Result<OnoConfig, OnoConfigError> {
+    let mut sources: HashMap<String, ConfigSource> = HashMap::new();
+    let mut get_val = |key: &str, flag: Option<&String>| {
+        let (value, source) = if let Some(v) = flag {
+            (Some(v.clone()), ConfigSource::Flag)
+        } else if let Some(v) = get_env(&get_env_name(key)) {
+            (Some(v), ConfigSource::Env)
+        } else if let Some(v) = file_map.get(key).and_then(|v| v.as_str()) {
+            (Some(v.to_string()), ConfigSource::File)
+        } else {
+            let v = default_map.get(key).and_then(|v| v.as_str());
+            (v.map(String::from), ConfigSource::Default)
+        };
+
+        sources.insert(key.to_string(), source);
+        value
+    };
+
+    let data_dir = get_val("data_dir", overrides.data_dir.as_ref());
+    let history_file = get_val("history_file", None);
+    let editor = get_val("editor", None);
+
+    // data_dir given as flag or env replaces the whole list, otherwise data_dirs wins over it
+    let data_dir_source = sources.get("data_dir").copied();
+    let dir_entries = file_map.get("data_dirs").and_then(|v| v.as_vec());
+    let mut data_dirs: Vec<DataDir> = match (data_dir_source, dir_entries) {
+        (Some(ConfigSource::File | ConfigSource::Default), Some(entries))
+            if !entries.is_empty() =>
+        {
+            sources.insert("data_dirs".to_string(), ConfigSource::File);
+            entries.iter().map(|e| parse_data_dir(e)).collect()
+        }
+        _ => {
+            if let Some(source) = data_dir_source {
+                sources.insert("data_dirs".to_string(), source);
+            }
+            data_dir.iter().map(|p| parse_data_dir(p)).collect()
+        }
+    };
+
+    let home_dir = dirs::home_dir();
+    let expand = |key: &str, value: &str| {
+        let base_dir = match sources.get(key) {
+            Some(ConfigSource::File) => path.parent(),
+            _ => None,
+        };
+
+        path_expander::expand_path(value, &get_env, home_dir.as_deref(), base_dir).map_err(
+            |variable| OnoConfigError::UnresolvedVariable {
+                key: key.to_string(),
+                variable,
+            },
+        )
+    };
+
+    let data_dir = data_dir.map(|v| expand("data_dir", &v)).transpose()?;
+    let history_file = history_file
+        .map(|v| expand("history_file", &v))
+        .transpose()?;
+    for dir in data_dirs.iter_mut() {
+        dir.path = expand("data_dirs", &dir.path)?;
+    }
+
+    let danger_rules = file_map
+        .get("danger_rules")
+        .and_then(|v| v.as_vec())
+        .map(|entries| entries.iter().map(|e| parse_danger_rule(e)).collect())
+        .unwrap_or_default();
+
     Ok(OnoConfig {
-        data_dir: get_val("data_dir"),
-        history_file: get_val("history_file"),
-        editor: get_val("editor"),
+        data_dir,
+        history_file,
+        editor,
+        data_dirs,
+        danger_rules,
+        path,
+        sources,
     })
 }
 
+/// Parse a `data_dirs` entry, `<path>` optionally followed by `| label=<label>` and `| readonly`
+fn parse_data_dir(entry: &str) -> DataDir {
+    let mut parts = entry.split('|').map(str::trim);
+    let mut data_dir = DataDir {
+        path: parts.next().unwrap_or_default().to_string(),
+        label: None,
+        read_only: false,
+    };
+
+    for option in parts {
+        match option.split_once('=') {
+            Some(("label", label)) => data_dir.label = Some(label.trim().to_string()),
+            None if option == "readonly" => data_dir.read_only = true,
+            _ => log::warn!("Unknown data_dirs option `{option}` for {}", data_dir.path),
+        }
+    }
+
+    data_dir
+}
+
+/// Parse a `danger_rules` entry, `<pattern>` optionally followed by `| reason=<reason>`
+fn parse_danger_rule(entry: &str) -> Rule {
+    let mut parts = entry.split('|').map(str::trim);
+    let pattern = parts.next().unwrap_or_default();
+    let mut rule = Rule::new(pattern, &format!("matches `{pattern}`"));
+
+    for option in parts {
+        match option.split_once('=') {
+            Some(("reason", reason)) => rule.reason = reason.trim().to_string(),
+            _ => log::warn!("Unknown danger_rules option `{option}` for {pattern}"),
+        }
+    }
+
+    rule
+}
+
+/// Write a config key to the config file, creates the file if it doesn't exist
+pub fn set_config_value(path: &Path, key: &str, value: &str) -> Result<(), OnoConfigError> {
+    if !CONFIG_KEYS.contains(&key) {
+        return Err(OnoConfigError::UnknownKey(key.to_string()));
+    }
+
+    let content = fs::read_to_string(path).unwrap_or_default();
+    let content = yaml_parser::set_yaml_value(&content, key, value);
+
+    path.parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(path, content))
+        .map_err(|err| {
+            log::error!("Error occurred while writing the config: {err:?}");
+            OnoConfigError::FileWriteError
+        })
+}
+
+/// Create a commented config file with the given values, keys without value are commented out
+pub fn create_config_file(
+    path: &Path,
+    values: &[(&str, Option<&str>)],
+) -> Result<(), OnoConfigError> {
+    let content = format_config_file(values);
+
+    path.parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(path, content))
+        .map_err(|err| {
+            log::error!("Error occurred while creating the config: {err:?}");
+            OnoConfigError::FileWriteError
+        })
+}
+
+fn format_config_file(values: &[(&str, Option<&str>)]) -> String {
+    let mut content = String::from("# ono configuration\n");
+
+    for (key, value) in values {
+        content.push('\n');
+        if let Some((_, comment)) = CONFIG_KEY_COMMENTS.iter().find(|(k, _)| k == key) {
+            content.push_str(&format!("# {comment}\n"));
+        }
+
+        match value {
+            Some(v) => content = yaml_parser::set_yaml_value(&content, key, v),
+            None => content.push_str(&format!("# {key}:\n")),
+        }
+    }
+
+    content
+}
+
+/// Get the config file path, `--config` flag and `ONO_CONFIG` replace the default location
+pub fn get_config_path(overrides: &ConfigOverrides) -> PathBuf {
+    overrides
+        .config_file
+        .clone()
+        .or_else(|| env::var(CONFIG_ENV).ok())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| get_full_config_path(true))
+}
+
+/// Get the environment variable that overrides the config key, e.g. `ONO_DATA_DIR`
+fn get_env_name(key: &str) -> String {
+    format!("ONO_{}", key.to_uppercase())
+}
+
 fn get_full_config_path(include_filename: bool) -> PathBuf {
     let mut path = dirs::config_dir().unwrap_or(PathBuf::from("./"));
     path.push(CONFIG_FOLDER);
@@ -66,3 +392,206 @@ fn get_default_config() -> HashMap<String, YamlValue> {
     result.insert("data_dir".to_string(), YamlValue::String(data_dir));
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_map(pairs: &[(&str, &str)]) -> HashMap<String, YamlValue> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), YamlValue::String(v.to_string())))
+            .collect()
+    }
+
+    #[test]
+    fn it_resolves_config_by_precedence() {
+        let file_map = to_map(&[("data_dir", "/file/data"), ("editor", "nano")]);
+        let default_map = to_map(&[
+            ("data_dir", "/default/data"),
+            ("editor", "vi"),
+            ("history_file", "/default/history"),
+        ]);
+        let get_env = |name: &str| match name {
+            "ONO_DATA_DIR" => Some("/env/data".to_string()),
+            "ONO_EDITOR" => Some("nvim".to_string()),
+            _ => None,
+        };
+        let overrides = ConfigOverrides {
+            config_file: None,
+            data_dir: Some("/flag/data".to_string()),
+        };
+
+        let config = resolve_config(
+            PathBuf::from("ono.yaml"),
+            &file_map,
+            &default_map,
+            get_env,
+            &overrides,
+        )
+        .unwrap();
+
+        assert_eq!(config.get("data_dir"), Some("/flag/data"));
+        assert_eq!(config.source("data_dir"), Some(ConfigSource::Flag));
+        assert_eq!(config.get("editor"), Some("nvim"));
+        assert_eq!(config.source("editor"), Some(ConfigSource::Env));
+        assert_eq!(config.get("history_file"), Some("/default/history"));
+        assert_eq!(config.source("history_file"), Some(ConfigSource::Default));
+    }
+
+    #[test]
+    fn it_formats_config_file() {
+        let content = format_config_file(&[
+            ("data_dir", Some("/home/alice/ono")),
+            ("history_file", None),
+        ]);
+
+        assert_eq!(
+            content,
+            "# ono configuration
+
+# Location of command snippets
+data_dir: /home/alice/ono
+
+# Location of your shell history file
+# history_file:
+"
+        );
+
+        let map = yaml_parser::parse_yaml_from_string(&content).unwrap();
+        assert_eq!(map["data_dir"].as_str(), Some("/home/alice/ono"));
+        assert!(!map.contains_key("history_file"));
+    }
+
+    #[test]
+    fn it_parses_data_dir_entries() {
+        let test_cases = vec![
+            ("/home/alice/ono", None, false),
+            ("/srv/team | label=team | readonly", Some("team"), true),
+            ("/srv/ops|readonly", None, true),
+        ];
+
+        for (input, label, read_only) in test_cases {
+            let data_dir = parse_data_dir(input);
+            assert_eq!(data_dir.label.as_deref(), label);
+            assert_eq!(data_dir.read_only, read_only);
+            assert!(data_dir.path.starts_with('/') && !data_dir.path.contains('|'));
+        }
+    }
+
+    #[test]
+    fn it_resolves_data_dirs() {
+        let mut file_map = to_map(&[("data_dir", "/file/data")]);
+        file_map.insert(
+            "data_dirs".to_string(),
+            YamlValue::Array(vec![
+                "/personal".to_string(),
+                "/team | label=team | readonly".to_string(),
+            ]),
+        );
+        let resolve = |overrides: &ConfigOverrides| {
+            resolve_config(
+                PathBuf::from("ono.yaml"),
+                &file_map,
+                &HashMap::new(),
+                |_| None,
+                overrides,
+            )
+            .unwrap()
+        };
+
+        let config = resolve(&ConfigOverrides::default());
+        assert_eq!(config.data_dirs.len(), 2);
+        assert_eq!(config.data_dirs[0].path, "/personal");
+        assert!(config.data_dirs[1].read_only);
+
+        let overrides = ConfigOverrides {
+            config_file: None,
+            data_dir: Some("/tmp/ono".to_string()),
+        };
+        let config = resolve(&overrides);
+        assert_eq!(config.data_dirs.len(), 1);
+        assert_eq!(config.data_dirs[0].path, "/tmp/ono");
+        assert_eq!(config.source("data_dirs"), Some(ConfigSource::Flag));
+    }
+
+    #[test]
+    fn it_parses_danger_rules() {
+        let test_cases = vec![
+            (
+                "git push * main | reason=pushes to main",
+                Rule::new("git push * main", "pushes to main"),
+            ),
+            (
+                "helm uninstall",
+                Rule::new("helm uninstall", "matches `helm uninstall`"),
+            ),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(parse_danger_rule(input), expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn it_resolves_file_values_over_defaults() {
+        let file_map = to_map(&[("editor", "nano")]);
+        let default_map = to_map(&[("editor", "vi")]);
+
+        let config = resolve_config(
+            PathBuf::from("ono.yaml"),
+            &file_map,
+            &default_map,
+            |_| None,
+            &ConfigOverrides::default(),
+        )
+        .unwrap();
+
+        assert_eq!(config.get("editor"), Some("nano"));
+        assert_eq!(config.source("editor"), Some(ConfigSource::File));
+        assert_eq!(config.get("data_dir"), None);
+    }
+
+    #[test]
+    fn it_expands_path_values() {
+        let file_map = to_map(&[("data_dir", "snippets"), ("history_file", "$HISTDIR/hist")]);
+        let get_env = |name: &str| (name == "HISTDIR").then(|| "/var/hist".to_string());
+
+        let config = resolve_config(
+            PathBuf::from("/home/alice/.config/ono/ono.yaml"),
+            &file_map,
+            &HashMap::new(),
+            get_env,
+            &ConfigOverrides::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.get("data_dir"),
+            Some("/home/alice/.config/ono/snippets")
+        );
+        assert_eq!(config.data_dirs[0].path, "/home/alice/.config/ono/snippets");
+        assert_eq!(config.get("history_file"), Some("/var/hist/hist"));
+    }
+
+    #[test]
+    fn it_fails_on_unresolved_variables() {
+        let file_map = to_map(&[("data_dir", "${NOTES}/ono")]);
+
+        let result = resolve_config(
+            PathBuf::from("ono.yaml"),
+            &file_map,
+            &HashMap::new(),
+            |_| None,
+            &ConfigOverrides::default(),
+        );
+
+        assert_eq!(
+            result.unwrap_err(),
+            OnoConfigError::UnresolvedVariable {
+                key: "data_dir".to_string(),
+                variable: "NOTES".to_string()
+            }
+        );
+    }
+}
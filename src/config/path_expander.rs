@@ -0,0 +1,124 @@
+use std::path::{Path, PathBuf};
+
+/// Expand `~`, `$VAR` and `${VAR}` in the path.
+/// Relative paths are joined to `base_dir` when it's given.
+/// Returns the name of the variable as error if it isn't set.
+pub fn expand_path(
+    value: &str,
+    get_env: impl Fn(&str) -> Option<String>,
+    home_dir: Option<&Path>,
+    base_dir: Option<&Path>,
+) -> Result<String, String> {
+    let expanded = expand_variables(&expand_tilde(value, home_dir), get_env)?;
+    let path = PathBuf::from(&expanded);
+
+    match base_dir {
+        Some(base) if path.is_relative() => Ok(base.join(path).to_string_lossy().into_owned()),
+        _ => Ok(expanded),
+    }
+}
+
+/// Replace leading `~` with the home directory, `~user` is not supported and left as is
+fn expand_tilde(value: &str, home_dir: Option<&Path>) -> String {
+    let Some(home) = home_dir else {
+        return value.to_string();
+    };
+
+    match value.strip_prefix('~') {
+        Some("") => home.to_string_lossy().into_owned(),
+        Some(rest) if rest.starts_with(['/', '\\']) => format!("{}{rest}", home.display()),
+        _ => value.to_string(),
+    }
+}
+
+fn expand_variables(
+    value: &str,
+    get_env: impl Fn(&str) -> Option<String>,
+) -> Result<String, String> {
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(idx) = rest.find('$') {
+        result.push_str(&rest[..idx]);
+        let after = &rest[idx + 1..];
+
+        let (name, consumed) = if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            }
+        } else {
+            let end = after.find(|c| !is_name_char(c)).unwrap_or(after.len());
+            (&after[..end], end)
+        };
+
+        if name.is_empty() {
+            // not a variable, keep the dollar sign
+            result.push('$');
+            rest = after;
+            continue;
+        }
+
+        result.push_str(&get_env(name).ok_or_else(|| name.to_string())?);
+        rest = &after[consumed..];
+    }
+
+    result.push_str(rest);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_env(name: &str) -> Option<String> {
+        match name {
+            "HOME" => Some("/home/alice".to_string()),
+            "NOTES" => Some("notes".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn it_expands_paths() {
+        let home = Path::new("/home/alice");
+        let base = Path::new("/home/alice/.config/ono");
+        let test_cases = vec![
+            ("~", "/home/alice"),
+            ("~/notes/ono", "/home/alice/notes/ono"),
+            ("~bob/ono", "/home/alice/.config/ono/~bob/ono"),
+            ("$HOME/.bash_history", "/home/alice/.bash_history"),
+            ("${HOME}/${NOTES}/ono", "/home/alice/notes/ono"),
+            ("/srv/$NOTES-backup", "/srv/notes-backup"),
+            ("/srv/cost$/ono", "/srv/cost$/ono"),
+            ("snippets", "/home/alice/.config/ono/snippets"),
+            ("/abs/path", "/abs/path"),
+        ];
+
+        for (input, expected) in test_cases {
+            let result = expand_path(input, get_env, Some(home), Some(base));
+            assert_eq!(result.as_deref(), Ok(expected), "input: {input}");
+        }
+    }
+
+    #[test]
+    fn it_keeps_relative_paths_without_base_dir() {
+        assert_eq!(
+            expand_path("snippets", get_env, None, None),
+            Ok("snippets".to_string())
+        );
+    }
+
+    #[test]
+    fn it_fails_on_unset_variables() {
+        let test_cases = vec![("$MISSING/ono", "MISSING"), ("${NOPE}", "NOPE")];
+
+        for (input, expected) in test_cases {
+            assert_eq!(
+                expand_path(input, get_env, None, None),
+                Err(expected.to_string())
+            );
+        }
+    }
+}
@@ -5,6 +5,8 @@ use std::{collections::HashMap, fs, path::Path};
 pub enum YamlValue {
     String(String),
     Array(Vec<String>),
+    /// Indented `key: value` pairs under a key, in order
+    Map(Vec<(String, String)>),
     Null,
 }
 
@@ -22,6 +24,13 @@ impl YamlValue {
             _ => None,
         }
     }
+
+    pub fn as_map(&self) -> Option<&[(String, String)]> {
+        match self {
+            YamlValue::Map(m) => Some(m.as_slice()),
+            _ => None,
+        }
+    }
 }
 
 /// Represent errors for parsing yaml
@@ -45,11 +54,26 @@ pub fn parse_yaml_from_string(
 ) -> Result<HashMap<String, YamlValue>, YamlParserError> {
     let mut result: HashMap<String, YamlValue> = HashMap::new();
     let mut multiline_key: Option<String> = None;
-    for line in yaml_content.lines() {
-        let line = remove_yaml_comments(line);
+    for raw_line in yaml_content.lines() {
+        let is_indented = raw_line.starts_with([' ', '\t']);
+        let line = remove_yaml_comments(raw_line);
         let (key, value) = get_key_value(line);
 
         match (key, value) {
+            //   key: value, under a key without value
+            (Some(k), Some(v)) if is_indented && !k.starts_with('-') => {
+                let Some(ml_key) = &multiline_key else {
+                    continue;
+                };
+                // a key without value is read as an empty array until its first item
+                if matches!(result.get(ml_key), Some(YamlValue::Array(arr)) if arr.is_empty()) {
+                    result.insert(ml_key.clone(), YamlValue::Map(vec![]));
+                }
+                if let Some(YamlValue::Map(map)) = result.get_mut(ml_key) {
+                    map.push((k.to_string(), v.to_string()));
+                }
+            }
+
             // key: value
             (Some(k), Some(v)) => {
                 let s_val = YamlValue::String(v.to_string());
@@ -85,6 +109,71 @@ pub fn parse_yaml_from_string(
     }
 }
 
+/// Set a top level key in yaml content, replacing the existing value or appending the key.
+/// Everything else in the content, including comments, is kept as is.
+pub fn set_yaml_value(yaml_content: &str, key: &str, value: &str) -> String {
+    let new_lines = vec![format!("{key}: {}", format_yaml_value(value))];
+    set_yaml_lines(yaml_content, key, new_lines)
+}
+
+/// Set a top level key to a list of values, see `set_yaml_value`
+pub fn set_yaml_list(yaml_content: &str, key: &str, values: &[String]) -> String {
+    let new_lines = std::iter::once(format!("{key}:"))
+        .chain(
+            values
+                .iter()
+                .map(|v| format!("  - {}", format_yaml_value(v))),
+        )
+        .collect();
+    set_yaml_lines(yaml_content, key, new_lines)
+}
+
+fn set_yaml_lines(yaml_content: &str, key: &str, new_lines: Vec<String>) -> String {
+    let mut lines: Vec<String> = vec![];
+    let mut replaced = false;
+    let mut skip_items = false;
+
+    for line in yaml_content.lines() {
+        let is_indented = line.starts_with([' ', '\t']);
+        if skip_items {
+            // drop the array items of the replaced key
+            if is_indented || remove_yaml_comments(line).starts_with('-') {
+                continue;
+            }
+            skip_items = false;
+        }
+
+        if !is_indented && !replaced && get_key_value(remove_yaml_comments(line)).0 == Some(key) {
+            lines.extend(new_lines.iter().cloned());
+            replaced = true;
+            skip_items = true;
+            continue;
+        }
+
+        lines.push(line.to_string());
+    }
+
+    if !replaced {
+        lines.extend(new_lines);
+    }
+
+    let mut result = lines.join("\n");
+    result.push('\n');
+    result
+}
+
+/// Format value to be written to yaml, adds qoutes if the value can't be read back as is
+fn format_yaml_value(value: &str) -> String {
+    let needs_quotes =
+        value.is_empty() || value.trim() != value || value.starts_with('-') || value.contains('#');
+
+    if needs_quotes {
+        format!("\"{value}\"")
+    } else {
+        value.to_string()
+    }
+}
+
 /// Clear value from yaml, trims qoutes, dashes, and spaces
 fn clear_yaml_value(value: &str) -> &str {
     value
@@ -187,6 +276,33 @@ bar:
         }
     }
 
+    #[test]
+    fn it_parses_maps() {
+        let yaml = "
+env:
+  RUST_LOG: debug
+  DATABASE_URL: \"postgres://localhost:5432\"
+cwd: ~/src/ono
+";
+
+        let map = parse_yaml_from_string(yaml).unwrap();
+        assert_eq!(
+            map["env"].as_map(),
+            Some(
+                [
+                    ("RUST_LOG".to_string(), "debug".to_string()),
+                    (
+                        "DATABASE_URL".to_string(),
+                        "postgres://localhost:5432".to_string()
+                    ),
+                ]
+                .as_slice()
+            )
+        );
+        assert_eq!(map["cwd"].as_str(), Some("~/src/ono"));
+        assert!(!map.contains_key("RUST_LOG"));
+    }
+
     #[test]
     fn it_clears_yaml_value() {
         let test_cases = vec![
@@ -237,4 +353,42 @@ bar:
             assert_eq!(&remove_yaml_comments(input), &expected);
         }
     }
+
+    #[test]
+    fn it_sets_yaml_value() {
+        let test_cases = vec![
+            ("", "editor: vim\n"),
+            ("editor: nano\n", "editor: vim\n"),
+            (
+                "# my config\neditor: nano # comment\ndata_dir: /tmp\n",
+                "# my config\neditor: vim\ndata_dir: /tmp\n",
+            ),
+            ("data_dir: /tmp", "data_dir: /tmp\neditor: vim\n"),
+            (
+                "editor:\n  - nano\n  - vi\ndata_dir: /tmp\n",
+                "editor: vim\ndata_dir: /tmp\n",
+            ),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(set_yaml_value(input, "editor", "vim"), expected);
+        }
+    }
+
+    #[test]
+    fn it_reads_back_set_values() {
+        let yaml = set_yaml_value("", "history_file", "/tmp/#history");
+        let map = parse_yaml_from_string(&yaml).unwrap();
+        assert_eq!(map["history_file"].as_str(), Some("/tmp/#history"));
+    }
+
+    #[test]
+    fn it_sets_yaml_list() {
+        let tags = vec!["docker".to_string(), "cleanup".to_string()];
+        let yaml = set_yaml_list("tags:\n  - old\nused: 1\n", "tags", &tags);
+        assert_eq!(yaml, "tags:\n  - docker\n  - cleanup\nused: 1\n");
+
+        let map = parse_yaml_from_string(&yaml).unwrap();
+        assert_eq!(map["tags"].as_vec(), Some(tags.as_slice()));
+    }
 }
@@ -1,34 +1,89 @@
+use std::env;
+
 use color_eyre::Result;
 
-use crate::tui::app::App;
+use crate::{
+    cli::Command,
+    store::NewSnippet,
+    tui::{app::App, form::SnippetForm},
+};
 
+mod cli;
+mod clipboard;
+mod commands;
 mod config;
 mod os_helper;
 mod store;
+#[cfg(test)]
+mod test_dir;
 mod tui;
 
 fn main() -> Result<()> {
     env_logger::init();
     let _ = color_eyre::install();
 
-    let ono_config = match config::get_config() {
-        Ok(cfg) => {
-            log::debug!("Config retrieved: {cfg:?}");
-            cfg
+    let cli = cli::parse_args(env::args().skip(1))?;
+
+    let ono_config = config::get_config(&cli.overrides)?;
+    log::debug!("Config retrieved: {ono_config:?}");
+
+    // values to populate the new snippet form with
+    let new_snippet = match cli.command {
+        Command::Tui => None,
+        Command::Clip => Some(NewSnippet {
+            command: clipboard::read()?,
+            ..Default::default()
+        }),
+        Command::Add(args) if args.title.is_some() => {
+            return commands::add::run(args, &ono_config);
         }
-        Err(err) => {
-            log::error!("Error occurred while getting the config: {err:?}");
-            panic!("")
+        Command::Add(args) => Some(commands::add::to_new_snippet(args)?),
+        Command::Config(config_command) => {
+            return commands::config::run(config_command, &ono_config);
+        }
+        Command::Setup => return commands::setup::run(&ono_config),
+        Command::Import(args) => return commands::import::run(args, &ono_config),
+        Command::Export(args) => return commands::export::run(args, &ono_config),
+        Command::Sync => return commands::sync::run(&ono_config),
+        Command::Stats => return commands::stats::run(&ono_config),
+        Command::Mv(args) => return commands::mv::run(args, &ono_config),
+        Command::Run(name) => {
+            let code = commands::run::run(&name, &ono_config)?;
+            std::process::exit(code);
+        }
+    };
+
+    if cli.rebuild_index {
+        store::index::remove_index(&store::index::get_index_path())?;
+    }
+
+    // first run, create the data directories along with a couple of examples
+    for data_dir in ono_config.data_dirs.iter().filter(|d| !d.read_only) {
+        if store::init_data_dir(&data_dir.path)? {
+            eprintln!(
+                "Created data directory {}, run `ono setup` to change it.",
+                data_dir.path
+            );
         }
+    }
+
+    // snippets are loaded in the background once the app is running
+    let mut app = App {
+        data_dir: ono_config.writable_data_dir().map(String::from),
+        data_dirs: ono_config.data_dirs.clone(),
+        danger_rules: ono_config.danger_rules.clone(),
+        form: new_snippet.map(|new_snippet| SnippetForm::new(&new_snippet)),
+        ..Default::default()
     };
 
-    let mut app = App::default();
-    if let Some(data_dir) = ono_config.data_dir {
-        app.snippets = store::load_snippets(&data_dir)?;
-        app.list_state.select_first();
+    render_tui(&mut app)?;
+
+    if let Some(snippet) = app.selected.map(|idx| &mut app.snippets[idx]) {
+        let code = commands::run::run_snippet(snippet, &ono_config.danger_rules)?;
+        std::process::exit(code);
     }
 
-    render_tui(&mut app)
+    Ok(())
 }
 
 fn render_tui(app: &mut App) -> Result<()> {
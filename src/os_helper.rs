@@ -1,4 +1,8 @@
-use std::{env, path::PathBuf};
+use std::{
+    env, io,
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus},
+};
 
 #[derive(Debug)]
 enum OS {
@@ -38,6 +42,21 @@ pub fn get_data_dir() -> String {
         .unwrap_or(format!("./{}", DATA_DIR))
 }
 
+/// Directory for local state that shouldn't be synced along with the snippets
+pub fn get_state_dir() -> PathBuf {
+    dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .unwrap_or(PathBuf::from("./"))
+        .join(DATA_DIR)
+}
+
+/// Directory for files that can be rebuilt any time, e.g. the snippet index
+pub fn get_cache_dir() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or(PathBuf::from("./"))
+        .join(DATA_DIR)
+}
+
 pub fn get_editor() -> String {
     log::info!("Getting user's default editor");
     match get_os() {
@@ -48,13 +67,17 @@ pub fn get_editor() -> String {
     }
 }
 
-fn get_shell() -> Shell {
-    log::info!("Getting user's default shell");
-    let shell_str = match get_os() {
+fn get_shell_path() -> String {
+    match get_os() {
         OS::Linux => env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string()),
         OS::Mac => env::var("SHELL").unwrap_or_else(|_| "/bin/zsh".to_string()),
         OS::Windows => env::var("COMSPEC").unwrap_or_else(|_| "cmd.exe".to_string()),
-    };
+    }
+}
+
+fn get_shell() -> Shell {
+    log::info!("Getting user's default shell");
+    let shell_str = get_shell_path();
     log::info!("User's shell: {shell_str}");
 
     let lower = shell_str.to_lowercase();
@@ -111,3 +134,73 @@ pub fn get_history_file() -> Option<String> {
     log::info!("User's shell set as {shell:?}");
     shell.map(|v| v.into_os_string().into_string().unwrap())
 }
+
+/// Open the file in the given editor and wait for it to exit.
+/// Editor can include arguments, e.g. `code --wait`
+pub fn open_in_editor(editor: &str, path: &Path) -> io::Result<ExitStatus> {
+    let mut parts = editor.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "editor is empty"))?;
+
+    log::info!("Opening {path:?} with {editor}");
+    Command::new(program).args(parts).arg(path).status()
+}
+
+/// Run the command with the user's shell, in `cwd` when given and with the extra
+/// environment variables. Output goes to the terminal, returns the exit status.
+pub fn run_in_shell(
+    command: &str,
+    cwd: Option<&Path>,
+    env: &[(String, String)],
+) -> io::Result<ExitStatus> {
+    let shell = get_shell_path();
+    let kind = get_shell();
+    let args: &[&str] = match kind {
+        Shell::Pwrshl => &["-NoProfile", "-Command"],
+        Shell::Cmd => &["/C"],
+        Shell::Bash | Shell::Zsh | Shell::Fish | Shell::Unknown => &["-c"],
+    };
+
+    log::info!("Running {command:?} with {shell} in {cwd:?}");
+    let mut process = Command::new(&shell);
+    process.args(args);
+    push_command_arg(&mut process, &kind, command);
+    process.envs(env.iter().map(|(k, v)| (k, v)));
+    if let Some(cwd) = cwd {
+        process.current_dir(cwd);
+    }
+    process.status()
+}
+
+/// cmd parses its command line itself and doesn't undo the quoting of `arg`,
+/// so the command is passed to it as is
+#[cfg(windows)]
+fn push_command_arg(process: &mut Command, shell: &Shell, command: &str) {
+    use std::os::windows::process::CommandExt;
+
+    match shell {
+        Shell::Cmd => process.raw_arg(command),
+        _ => process.arg(command),
+    };
+}
+
+#[cfg(not(windows))]
+fn push_command_arg(process: &mut Command, _shell: &Shell, command: &str) {
+    process.arg(command);
+}
+
+/// Find the executable in PATH
+pub fn find_executable(name: &str) -> Option<PathBuf> {
+    let paths = env::var_os("PATH")?;
+    env::split_paths(&paths).find_map(|dir| {
+        let path = dir.join(name);
+        if path.is_file() {
+            return Some(path);
+        }
+
+        // executables have an extension on Windows
+        let path = path.with_extension("exe");
+        (cfg!(target_os = "windows") && path.is_file()).then_some(path)
+    })
+}
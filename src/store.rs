@@ -1,41 +1,962 @@
 use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
     ffi::OsStr,
-    fs, io,
+    fmt, fs, io,
     path::{Path, PathBuf},
+    thread,
 };
 
 use chrono::{DateTime, Local};
 
-use crate::config::yaml_parser;
+use crate::config::{DataDir, yaml_parser};
 
-#[derive(Debug, Default)]
+mod atomic_file;
+pub mod danger;
+mod file_name;
+pub mod history;
+mod id;
+pub mod index;
+pub mod placeholder;
+pub mod rank;
+pub mod secret;
+mod sidecar;
+pub mod watcher;
+mod wikilink;
+
+use atomic_file::FileStamp;
+
+/// How many times a write is retried when the file keeps changing under us
+const WRITE_ATTEMPTS: usize = 3;
+
+/// Snippets written to a new data directory, file name and content
+static EXAMPLE_SNIPPETS: [(&str, &str); 2] = [
+    (
+        "List directory.md",
+        "---
+description: List directory with details and colors
+alias: ll
+---
+
+```bash
+ls -alFh --color=auto
+```
+",
+    ),
+    (
+        "Disk usage.md",
+        "---
+description: Show disk usage of mounted file systems in human readable format
+---
+
+```bash
+df -h
+```
+",
+    ),
+];
+
+#[derive(Debug, Default, Clone)]
 pub struct Snippet {
+    /// Stable id from the `id` frontmatter key, uses are recorded by it
+    pub id: String,
     pub title: String,
     pub description: String,
     pub command: String,
+    pub alias: Option<String>,
+    pub tags: Vec<String>,
     pub path: PathBuf,
-    pub used: u16,
+    pub used: u32,
     pub last_used: Option<DateTime<Local>>,
+    /// Flagged with `dangerous: true`, running it needs a confirmation
+    pub dangerous: bool,
+    /// Directory the command is run in, from the `cwd` frontmatter key
+    pub cwd: Option<String>,
+    /// Environment variables the command is run with, from the `env` frontmatter key
+    pub env: Vec<(String, String)>,
+    /// Reasons the danger rules flag the command for, set by `danger::flag_snippets`
+    pub risks: Vec<String>,
+    /// Label of the data directory the snippet is loaded from
+    pub source: Option<String>,
+    pub read_only: bool,
+    /// State of the file when it's loaded, to detect changes made by other programs
+    pub stamp: Option<FileStamp>,
+}
+
+impl Snippet {
+    /// Flagged in the frontmatter or by a danger rule
+    pub fn is_dangerous(&self) -> bool {
+        self.dangerous || !self.risks.is_empty()
+    }
+}
+
+/// Fields of a snippet to be created
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NewSnippet {
+    pub title: String,
+    pub description: String,
+    pub alias: Option<String>,
+    pub tags: Vec<String>,
+    pub command: String,
+}
+
+/// Existing snippet that clashes with a new snippet
+#[derive(Debug)]
+pub enum Duplicate<'a> {
+    Title(&'a Snippet),
+    Alias(&'a Snippet),
+}
+
+/// Snippets loaded from the data directories, along with the files that couldn't be loaded
+#[derive(Debug, Default)]
+pub struct LoadedSnippets {
+    pub snippets: Vec<Snippet>,
+    pub warnings: Vec<LoadWarning>,
+}
+
+/// File or directory that couldn't be read, the others are loaded regardless
+#[derive(Debug)]
+pub struct LoadWarning {
+    pub path: PathBuf,
+    pub error: io::Error,
+}
+
+impl fmt::Display for LoadWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.error)
+    }
+}
+
+/// Files outside the data directories that snippets are loaded with
+#[derive(Debug, Clone)]
+struct StateFiles {
+    /// Snippets parsed from the files, see `index`
+    index: PathBuf,
+    /// Ids of the snippets in read-only directories
+    ids: PathBuf,
+    /// Usage counters of the snippets in read-only directories
+    sidecar: PathBuf,
+}
+
+impl StateFiles {
+    fn get() -> Self {
+        StateFiles {
+            index: index::get_index_path(),
+            ids: sidecar::get_ids_path(),
+            sidecar: sidecar::get_sidecar_path(),
+        }
+    }
+}
+
+/// Load snippets from every data directory, earlier directories have priority on aliases.
+/// Only the files changed since the last load are parsed, the others come from the index.
+pub fn load_snippets(data_dirs: &[DataDir]) -> LoadedSnippets {
+    load_snippets_with_state(data_dirs, &StateFiles::get())
+}
+
+fn load_snippets_with_state(data_dirs: &[DataDir], state: &StateFiles) -> LoadedSnippets {
+    let mut result = LoadedSnippets::default();
+    let sidecar_usage = sidecar::load_usage(&state.sidecar);
+    let mut index = index::Index::load(&state.index);
+
+    // markdown files of every directory along with the directory they're in
+    let mut files: Vec<(&DataDir, PathBuf)> = vec![];
+    for data_dir in data_dirs {
+        if !Path::new(&data_dir.path).is_dir() {
+            log::warn!("Data directory {} doesn't exist, skipping", data_dir.path);
+            continue;
+        }
+
+        match list_snippet_files(Path::new(&data_dir.path), data_dirs) {
+            Ok(paths) => files.extend(paths.into_iter().map(|path| (data_dir, path))),
+            Err(error) => result.warnings.push(LoadWarning {
+                path: PathBuf::from(&data_dir.path),
+                error,
+            }),
+        }
+    }
+
+    let mut loaded: Vec<Option<Option<Snippet>>> =
+        files.iter().map(|(_, path)| index.get(path)).collect();
+    let not_indexed: Vec<usize> = (0..files.len()).filter(|&i| loaded[i].is_none()).collect();
+    let paths: Vec<&Path> = not_indexed.iter().map(|&i| files[i].1.as_path()).collect();
+
+    for (i, entry) in not_indexed.into_iter().zip(read_entries(&paths)) {
+        let path = &files[i].1;
+        match entry {
+            Ok(entry) => loaded[i] = Some(index.insert(path, entry)),
+            Err(error) => {
+                log::warn!("Error occurred while loading {path:?}: {error:?}");
+                result.warnings.push(LoadWarning {
+                    path: path.clone(),
+                    error,
+                });
+            }
+        }
+    }
+
+    for ((data_dir, _), snippet) in files.iter().zip(loaded) {
+        if let Some(mut snippet) = snippet.flatten() {
+            apply_data_dir(&mut snippet, data_dir);
+            result.snippets.push(snippet);
+        }
+    }
+
+    assign_ids(&mut result.snippets, &mut index, &state.ids);
+    for snippet in &mut result.snippets {
+        apply_sidecar_usage(snippet, &sidecar_usage);
+    }
+
+    if let Err(err) = index.save(&state.index) {
+        log::warn!("Error occurred while saving the snippet index: {err:?}");
+    }
+
+    resolve_alias_conflicts(&mut result.snippets, data_dirs);
+    result
+}
+
+/// Read and parse the files spread across threads, results are in the order of the paths
+fn read_entries(paths: &[&Path]) -> Vec<io::Result<index::Entry>> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = paths.len().div_ceil(threads).max(1);
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = paths
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|path| index::read_entry(path))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|err| std::panic::resume_unwind(err))
+            })
+            .collect()
+    })
+}
+
+/// Set the values that come from the data directory rather than the file
+fn apply_data_dir(snippet: &mut Snippet, data_dir: &DataDir) {
+    snippet.source = data_dir.label.clone();
+    snippet.read_only = data_dir.read_only;
+}
+
+/// Usage of read-only snippets comes from the sidecar file, older lines are keyed by path
+fn apply_sidecar_usage(snippet: &mut Snippet, sidecar_usage: &HashMap<String, sidecar::Usage>) {
+    if !snippet.read_only {
+        return;
+    }
+
+    let usage = sidecar_usage
+        .get(&snippet.id)
+        .or_else(|| sidecar_usage.get(snippet.path.to_string_lossy().as_ref()));
+    if let Some(usage) = usage {
+        snippet.used = usage.used;
+        snippet.last_used = usage.last_used;
+    }
+}
+
+/// Give an id to the snippets without one, and to the ones with the id of a higher
+/// priority snippet, e.g. a copied file. A file with the same content as a removed file
+/// is the removed file moved and keeps its id.
+/// Ids of read-only files are kept in the state directory, not in the files or the index.
+fn assign_ids(snippets: &mut [Snippet], index: &mut index::Index, ids_path: &Path) {
+    let removed_ids = index.removed_ids();
+    let mut read_only_ids = sidecar::load_ids(ids_path);
+    let loaded_read_only_ids = read_only_ids.clone();
+    let mut seen = HashSet::new();
+
+    for snippet in snippets.iter_mut() {
+        if snippet.id.is_empty() || seen.contains(&snippet.id) {
+            if snippet.read_only {
+                if let Err(err) = set_read_only_id(snippet, &mut read_only_ids, &seen) {
+                    log::warn!("Couldn't set the id of {:?}: {err}", snippet.path);
+                }
+            } else {
+                let moved_id = snippet
+                    .stamp
+                    .filter(|_| snippet.id.is_empty())
+                    .and_then(|stamp| removed_ids.get(&stamp.hash))
+                    .filter(|id| !seen.contains(*id))
+                    .cloned();
+
+                match write_id(snippet, moved_id, &seen) {
+                    Ok(stamp) => index.set_id(&snippet.path, &snippet.id, Some(stamp)),
+                    Err(err) => log::warn!("Couldn't set the id of {:?}: {err}", snippet.path),
+                }
+            }
+        }
+        seen.insert(snippet.id.clone());
+    }
+
+    // removed files had their chance to be found moved, unless their directory is unavailable
+    read_only_ids.retain(|path, _| path.exists() || !path.parent().is_some_and(Path::is_dir));
+    if read_only_ids != loaded_read_only_ids
+        && let Err(err) = sidecar::save_ids(ids_path, &read_only_ids)
+    {
+        log::warn!("Error occurred while saving the ids of read-only snippets: {err:?}");
+    }
+}
+
+/// Set the id of the snippet, the given id or one derived from the file that isn't taken,
+/// and write it to the frontmatter. Returns the new stamp of the file.
+fn write_id(
+    snippet: &mut Snippet,
+    id: Option<String>,
+    taken: &HashSet<String>,
+) -> io::Result<FileStamp> {
+    let (content, stamp) = atomic_file::read_stamped(&snippet.path)?;
+    snippet.id =
+        id.unwrap_or_else(|| id::derive_id(&snippet.path, &content, |id| taken.contains(id)));
+
+    let content = update_frontmatter(&content, &[("id", &snippet.id)]);
+    let new_stamp = atomic_file::write_if_unchanged(&snippet.path, &content, Some(stamp))?;
+    log::info!("Id {} is written to {:?}", snippet.id, snippet.path);
+    snippet.stamp = Some(new_stamp);
+    Ok(new_stamp)
 }
 
-pub fn load_snippets(data_dir: &str) -> io::Result<Vec<Snippet>> {
+/// Set the id of the read-only snippet to the one kept for its path, or the one of a removed
+/// file with the same content, otherwise a new one derived from the file that isn't taken
+fn set_read_only_id(
+    snippet: &mut Snippet,
+    ids: &mut HashMap<PathBuf, sidecar::ReadOnlyId>,
+    taken: &HashSet<String>,
+) -> io::Result<()> {
+    let (content, hash) = match snippet.stamp {
+        Some(stamp) => (None, stamp.hash),
+        None => {
+            let (content, stamp) = atomic_file::read_stamped(&snippet.path)?;
+            (Some(content), stamp.hash)
+        }
+    };
+
+    let is_free = |entry: &sidecar::ReadOnlyId| !taken.contains(&entry.id);
+    let moved_from = ids
+        .iter()
+        .find(|(path, entry)| entry.hash == hash && is_free(entry) && !path.exists())
+        .map(|(path, _)| path.clone());
+    let id = match ids.get(&snippet.path).filter(|entry| is_free(entry)) {
+        Some(entry) => entry.id.clone(),
+        None => match moved_from.and_then(|path| ids.remove(&path)) {
+            Some(entry) => entry.id,
+            None => {
+                let content = match content {
+                    Some(content) => content,
+                    None => fs::read_to_string(&snippet.path)?,
+                };
+                id::derive_id(&snippet.path, &content, |id| taken.contains(id))
+            }
+        },
+    };
+
+    ids.insert(
+        snippet.path.clone(),
+        sidecar::ReadOnlyId {
+            id: id.clone(),
+            hash,
+        },
+    );
+    snippet.id = id;
+    Ok(())
+}
+
+/// Reflect a change of the file on disk in the loaded snippets.
+/// The snippet is added, replaced or removed depending on the file's current state.
+pub fn reload_snippet(
+    snippets: &mut Vec<Snippet>,
+    data_dirs: &[DataDir],
+    path: &Path,
+) -> io::Result<()> {
+    reload_snippet_with_state(snippets, data_dirs, path, &StateFiles::get())
+}
+
+fn reload_snippet_with_state(
+    snippets: &mut Vec<Snippet>,
+    data_dirs: &[DataDir],
+    path: &Path,
+    state: &StateFiles,
+) -> io::Result<()> {
+    // watchers may report canonical paths, find the innermost directory by its canonical path
+    let Some(parent) = path.parent().and_then(|p| fs::canonicalize(p).ok()) else {
+        return Ok(());
+    };
+    let canonical_dirs: Vec<Option<PathBuf>> = data_dirs
+        .iter()
+        .map(|d| fs::canonicalize(&d.path).ok())
+        .collect();
+    let Some((priority, folder)) = canonical_dirs
+        .iter()
+        .enumerate()
+        .filter_map(|(i, dir)| Some((i, parent.strip_prefix(dir.as_ref()?).ok()?)))
+        .min_by_key(|(_, folder)| folder.components().count())
+    else {
+        return Ok(());
+    };
+    let is_hidden = folder
+        .components()
+        .any(|c| c.as_os_str().to_string_lossy().starts_with('.'));
+    let Some(file_name) = path.file_name().filter(|_| !is_hidden) else {
+        return Ok(());
+    };
+    let path = Path::new(&data_dirs[priority].path)
+        .join(folder)
+        .join(file_name);
+
+    let existing = snippets.iter().position(|s| s.path == path);
+    let mut reloaded = if path.is_file() && path.extension() == Some(OsStr::new("md")) {
+        let (content, stamp) = atomic_file::read_stamped(&path)?;
+        extract_snippet(&path, &content).map(|mut snippet| {
+            snippet.stamp = Some(stamp);
+            apply_data_dir(&mut snippet, &data_dirs[priority]);
+            snippet
+        })
+    } else {
+        None
+    };
+
+    if let Some(snippet) = &mut reloaded {
+        if snippet.id.is_empty() {
+            // the file keeps its previous id, or the id of the removed file it's moved from
+            let previous = existing.map(|idx| &snippets[idx]).or_else(|| {
+                snippets.iter().find(|s| {
+                    s.stamp.map(|st| st.hash) == snippet.stamp.map(|st| st.hash) && !s.path.exists()
+                })
+            });
+            let previous_id = previous.map(|s| s.id.clone()).filter(|id| !id.is_empty());
+            let taken = snippets
+                .iter()
+                .filter(|s| s.path != path)
+                .map(|s| s.id.clone())
+                .collect();
+            let result = if snippet.read_only {
+                let mut ids = sidecar::load_ids(&state.ids);
+                set_read_only_id(snippet, &mut ids, &taken)
+                    .and_then(|()| sidecar::save_ids(&state.ids, &ids))
+            } else {
+                write_id(snippet, previous_id, &taken).map(|_| ())
+            };
+            if let Err(err) = result {
+                log::warn!("Couldn't set the id of {:?}: {err}", snippet.path);
+            }
+        }
+
+        let sidecar_usage = sidecar::load_usage(&state.sidecar);
+        apply_sidecar_usage(snippet, &sidecar_usage);
+    }
+
+    match (existing, reloaded) {
+        (Some(idx), Some(snippet)) => snippets[idx] = snippet,
+        (Some(idx), None) => {
+            snippets.remove(idx);
+        }
+        // snippets may be ranked, aliases are resolved by the data directories' order
+        (None, Some(snippet)) => snippets.push(snippet),
+        (None, None) => {}
+    }
+
+    resolve_alias_conflicts(snippets, data_dirs);
+    Ok(())
+}
+
+/// Paths of the markdown files in the data directory and its folders, sorted so the load
+/// order is stable
+fn list_snippet_files(data_dir: &Path, data_dirs: &[DataDir]) -> io::Result<Vec<PathBuf>> {
     let md_extension = OsStr::new("md");
-    let mut result: Vec<Snippet> = vec![];
-
-    for entry in fs::read_dir(data_dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        if path.is_file() && path.extension() == Some(md_extension) {
-            let content = fs::read_to_string(&path)?;
-            if let Some(snippet) = extract_snippet(&path, &content) {
-                result.push(snippet);
+    let mut result: Vec<PathBuf> = vec![];
+
+    let folders =
+        std::iter::once(data_dir.to_path_buf()).chain(list_subfolders(data_dir, data_dirs)?);
+    for folder in folders {
+        for entry in fs::read_dir(folder)? {
+            let path = entry?.path();
+            if path.is_file() && path.extension() == Some(md_extension) {
+                result.push(path);
             }
         }
     }
 
+    result.sort();
+    Ok(result)
+}
+
+/// Folders in the directory and in its folders, outer ones first. Hidden folders such as
+/// `.git` or `.obsidian` are skipped, as are the ones that are data directories themselves.
+fn list_subfolders(dir: &Path, data_dirs: &[DataDir]) -> io::Result<Vec<PathBuf>> {
+    let mut folders = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_hidden = path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with('.'));
+        let is_data_dir = data_dirs.iter().any(|d| path == Path::new(&d.path));
+        if path.is_dir() && !is_hidden && !is_data_dir {
+            folders.push(path);
+        }
+    }
+    folders.sort();
+
+    let mut result = vec![];
+    for folder in folders {
+        let nested = list_subfolders(&folder, data_dirs)?;
+        result.push(folder);
+        result.extend(nested);
+    }
     Ok(result)
 }
 
+/// Folder a snippet can be moved to
+#[derive(Debug, Clone, PartialEq)]
+pub struct Folder {
+    /// Label or path of the data directory, followed by the folders inside it
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Writable data directories and the folders inside them
+pub fn list_folders(data_dirs: &[DataDir]) -> Vec<Folder> {
+    let mut result = vec![];
+    for data_dir in data_dirs.iter().filter(|d| !d.read_only) {
+        let root = Path::new(&data_dir.path);
+        let root_name = data_dir.label.as_deref().unwrap_or(&data_dir.path);
+        result.push(Folder {
+            name: root_name.to_string(),
+            path: root.to_path_buf(),
+        });
+
+        let subfolders = list_subfolders(root, data_dirs).unwrap_or_else(|err| {
+            log::warn!("Couldn't list the folders of {root:?}: {err:?}");
+            vec![]
+        });
+        for path in subfolders {
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            let names: Vec<_> = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect();
+            result.push(Folder {
+                name: format!("{root_name}/{}", names.join("/")),
+                path,
+            });
+        }
+    }
+    result
+}
+
+/// Write a new snippet file to the data directory
+pub fn create_snippet(data_dir: &str, new_snippet: &NewSnippet) -> io::Result<Snippet> {
+    let title = new_snippet.title.trim();
+    if title.is_empty() || new_snippet.command.trim().is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "title and command are required",
+        ));
+    }
+
+    let path = file_name::get_snippet_path(Path::new(data_dir), title);
+
+    // keep the title in frontmatter if the file name can't represent it
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy())
+        .unwrap_or_default();
+    let title_key = (stem != title).then_some(title);
+
+    let content = format_snippet(new_snippet, &id::new_id(), title_key);
+    let stamp = atomic_file::write_if_unchanged(&path, &content, None)?;
+    log::info!("Snippet created at {path:?}");
+
+    let mut snippet = extract_snippet(&path, &content)
+        .ok_or_else(|| io::Error::other("created snippet can't be parsed"))?;
+    snippet.stamp = Some(stamp);
+    Ok(snippet)
+}
+
+/// Snippet renamed or moved by `move_snippet`, along with the notes its links are updated in
+#[derive(Debug)]
+pub struct MovedSnippet {
+    pub snippet: Snippet,
+    pub updated_notes: Vec<PathBuf>,
+    /// Notes of the data directory the snippet is moved out of that still link to it.
+    /// Links can't point to another vault, so they're left as is.
+    pub stale_notes: Vec<PathBuf>,
+}
+
+/// Rename the snippet's file for the new title and move it to the folder, a data directory
+/// or a folder inside one. The folder is created if it doesn't exist.
+/// The title is kept in the frontmatter if the file name can't represent it.
+/// Links to the snippet in the notes of its data directory are updated, unless it's moved to
+/// another data directory.
+pub fn move_snippet(
+    snippet: &Snippet,
+    data_dirs: &[DataDir],
+    folder: &Path,
+    title: &str,
+) -> io::Result<MovedSnippet> {
+    let title = title.trim();
+    if title.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "title is required",
+        ));
+    }
+    let data_dir = find_data_dir(data_dirs, folder).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} isn't in a data directory", folder.display()),
+        )
+    })?;
+    if snippet.read_only || data_dir.read_only {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "snippets in read-only data directories can't be moved",
+        ));
+    }
+
+    let old_path = snippet.path.as_path();
+    fs::create_dir_all(folder)?;
+    let new_path = file_name::get_renamed_snippet_path(folder, title, old_path);
+    let (content, stamp) = atomic_file::read_stamped(old_path)?;
+
+    let stem = new_path
+        .file_stem()
+        .map(|s| s.to_string_lossy())
+        .unwrap_or_default();
+    let content = if stem != title || has_frontmatter_key(&content, "title") {
+        update_frontmatter(&content, &[("title", title)])
+    } else {
+        content
+    };
+
+    let is_same_name = new_path.parent() == old_path.parent()
+        && new_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            == old_path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_lowercase());
+    let new_stamp = if new_path == old_path {
+        atomic_file::write_if_unchanged(&new_path, &content, Some(stamp))?
+    } else if is_same_name {
+        // only the case changes, the file is renamed as writing a new one would clash with it
+        fs::rename(old_path, &new_path)?;
+        atomic_file::write_atomic(&new_path, &content)?
+    } else {
+        let new_stamp = atomic_file::write_if_unchanged(&new_path, &content, None)?;
+        fs::remove_file(old_path)?;
+        new_stamp
+    };
+    log::info!("Snippet moved from {old_path:?} to {new_path:?}");
+
+    // links are relative to the data directory, the vault in Obsidian terms
+    let mut updated_notes = vec![];
+    let mut stale_notes = vec![];
+    if new_path != old_path {
+        let new_vault = Path::new(&data_dir.path);
+        let old_vault = find_data_dir(data_dirs, old_path)
+            .map(|d| Path::new(&d.path))
+            .or_else(|| old_path.parent())
+            .unwrap_or(new_vault);
+        let old_link = old_path.strip_prefix(old_vault).unwrap_or(old_path);
+
+        if old_vault == new_vault {
+            let new_link = new_path.strip_prefix(new_vault).unwrap_or(&new_path);
+            updated_notes = wikilink::rewrite_links_in_dir(old_vault, old_link, new_link)?;
+        } else {
+            stale_notes = wikilink::find_links_in_dir(old_vault, old_link)?;
+        }
+    }
+
+    let mut moved = extract_snippet(&new_path, &content)
+        .ok_or_else(|| io::Error::other("moved snippet can't be parsed"))?;
+    moved.stamp = Some(new_stamp);
+    apply_data_dir(&mut moved, data_dir);
+    Ok(MovedSnippet {
+        snippet: moved,
+        updated_notes,
+        stale_notes,
+    })
+}
+
+/// Whether the frontmatter of the markdown content sets the key
+fn has_frontmatter_key(content: &str, key: &str) -> bool {
+    let mut lines = content.lines().map(str::trim_end);
+    lines.next() == Some("---")
+        && lines
+            .take_while(|line| *line != "---")
+            .any(|line| line.split_once(':').is_some_and(|(k, _)| k.trim() == key))
+}
+
+/// Format the new snippet as markdown, empty fields are left out of the frontmatter
+fn format_snippet(new_snippet: &NewSnippet, id: &str, title_key: Option<&str>) -> String {
+    let body = format!("```\n{}\n```\n", new_snippet.command.trim());
+    let mut frontmatter = String::new();
+
+    let values = [
+        ("id", id),
+        ("title", title_key.unwrap_or("")),
+        ("description", new_snippet.description.trim()),
+        ("alias", new_snippet.alias.as_deref().unwrap_or("").trim()),
+    ];
+    for (key, value) in values.into_iter().filter(|(_, v)| !v.is_empty()) {
+        frontmatter = yaml_parser::set_yaml_value(&frontmatter, key, value);
+    }
+
+    if !new_snippet.tags.is_empty() {
+        frontmatter = yaml_parser::set_yaml_list(&frontmatter, "tags", &new_snippet.tags);
+    }
+
+    format!("---\n{frontmatter}---\n\n{body}")
+}
+
+/// Find an existing snippet with the same title or alias, titles are compared case insensitive
+pub fn find_duplicate<'a>(
+    snippets: &'a [Snippet],
+    new_snippet: &NewSnippet,
+) -> Option<Duplicate<'a>> {
+    let title = new_snippet.title.trim();
+    if let Some(snippet) = snippets
+        .iter()
+        .find(|s| s.title.eq_ignore_ascii_case(title))
+    {
+        return Some(Duplicate::Title(snippet));
+    }
+
+    let alias = new_snippet.alias.as_deref()?;
+    snippets
+        .iter()
+        .find(|s| s.alias.as_deref() == Some(alias))
+        .map(Duplicate::Alias)
+}
+
+/// Another snippet with the title, compared case insensitive, e.g. before renaming the snippet
+pub fn find_title_clash<'a>(
+    snippets: &'a [Snippet],
+    snippet: &Snippet,
+    title: &str,
+) -> Option<&'a Snippet> {
+    snippets
+        .iter()
+        .find(|s| s.path != snippet.path && s.title.eq_ignore_ascii_case(title.trim()))
+}
+
+/// Data directory the file or folder is in, directly or in one of its folders
+pub fn find_data_dir<'a>(data_dirs: &'a [DataDir], path: &Path) -> Option<&'a DataDir> {
+    data_dir_position(data_dirs, path).map(|idx| &data_dirs[idx])
+}
+
+/// Position of the innermost data directory the path is in, the first one if it's listed twice
+fn data_dir_position(data_dirs: &[DataDir], path: &Path) -> Option<usize> {
+    data_dirs
+        .iter()
+        .enumerate()
+        .filter(|(_, d)| path.starts_with(&d.path))
+        .min_by_key(|(_, d)| Reverse(Path::new(&d.path).components().count()))
+        .map(|(idx, _)| idx)
+}
+
+/// Find the snippet by its alias or id, or by its title ignoring case
+pub fn find_snippet<'a>(snippets: &'a mut [Snippet], name: &str) -> Option<&'a mut Snippet> {
+    let idx = snippets
+        .iter()
+        .position(|s| s.alias.as_deref() == Some(name) || s.id == name)
+        .or_else(|| {
+            snippets
+                .iter()
+                .position(|s| s.title.eq_ignore_ascii_case(name))
+        })?;
+    Some(&mut snippets[idx])
+}
+
+/// Create the data directory with example snippets if it doesn't exist.
+/// Returns whether the directory is created.
+pub fn init_data_dir(data_dir: &str) -> io::Result<bool> {
+    if Path::new(data_dir).exists() {
+        return Ok(false);
+    }
+
+    log::info!("Creating data directory {data_dir}");
+    fs::create_dir_all(data_dir)?;
+    write_example_snippets(data_dir)?;
+    Ok(true)
+}
+
+/// Write the example snippets to the data directory, existing files are left as is
+pub fn write_example_snippets(data_dir: &str) -> io::Result<()> {
+    for (file_name, content) in EXAMPLE_SNIPPETS {
+        let path = Path::new(data_dir).join(file_name);
+        match atomic_file::write_if_unchanged(&path, content, None) {
+            Err(err) if !atomic_file::is_conflict(&err) => return Err(err),
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Keep the alias only on the snippet of the highest priority data directory that has it.
+/// Snippets may be in any order, e.g. ranked, ties go to the first path in sort order.
+fn resolve_alias_conflicts(snippets: &mut [Snippet], data_dirs: &[DataDir]) {
+    let priority =
+        |snippet: &Snippet| data_dir_position(data_dirs, &snippet.path).unwrap_or(usize::MAX);
+
+    let mut owners: HashMap<String, usize> = HashMap::new();
+    for (idx, snippet) in snippets.iter().enumerate() {
+        let Some(alias) = &snippet.alias else {
+            continue;
+        };
+        let owner = owners.entry(alias.clone()).or_insert(idx);
+        let current = &snippets[*owner];
+        if (priority(snippet), &snippet.path) < (priority(current), &current.path) {
+            *owner = idx;
+        }
+    }
+
+    for (idx, snippet) in snippets.iter_mut().enumerate() {
+        if let Some(alias) = &snippet.alias
+            && owners[alias] != idx
+        {
+            log::warn!(
+                "Alias `{alias}` of {:?} is already used by a higher priority snippet",
+                snippet.path
+            );
+            snippet.alias = None;
+        }
+    }
+}
+
+/// Increase the usage counter of the snippet and save it.
+/// Snippets from read-only directories are saved to the local sidecar file.
+pub fn record_usage(snippet: &mut Snippet) -> io::Result<()> {
+    if snippet.read_only {
+        snippet.used = snippet.used.saturating_add(1);
+        snippet.last_used = Some(Local::now());
+
+        let sidecar_path = sidecar::get_sidecar_path();
+        let mut usage = sidecar::load_usage(&sidecar_path);
+        usage.remove(snippet.path.to_string_lossy().as_ref());
+        usage.insert(
+            snippet.id.clone(),
+            sidecar::Usage {
+                used: snippet.used,
+                last_used: snippet.last_used,
+            },
+        );
+        return sidecar::save_usage(&sidecar_path, &usage);
+    }
+
+    // the file may be changed since it's loaded, counters are merged into the current content
+    for _ in 0..WRITE_ATTEMPTS {
+        let (content, stamp) = atomic_file::read_stamped(&snippet.path)?;
+        if snippet.stamp.is_some_and(|s| s != stamp) {
+            log::info!("{:?} is changed since loaded, merging usage", snippet.path);
+        }
+
+        let file_used = extract_snippet(&snippet.path, &content).map_or(0, |s| s.used);
+        let used = file_used.max(snippet.used).saturating_add(1);
+        let last_used = Local::now();
+        let content = update_frontmatter(
+            &content,
+            &[
+                ("used", &used.to_string()),
+                ("last_used", &last_used.to_rfc3339()),
+            ],
+        );
+
+        match atomic_file::write_if_unchanged(&snippet.path, &content, Some(stamp)) {
+            Ok(new_stamp) => {
+                snippet.used = used;
+                snippet.last_used = Some(last_used);
+                snippet.stamp = Some(new_stamp);
+                return Ok(());
+            }
+            Err(err) if atomic_file::is_conflict(&err) => continue,
+            Err(err) => return Err(err),
+        }
+    }
+
+    Err(io::Error::other(atomic_file::ConflictError {
+        path: snippet.path.clone(),
+    }))
+}
+
+/// Merge two versions of a snippet file that differ only in their usage counters, e.g. when
+/// the snippet is used on two machines. The higher `used` and the later `last_used` win.
+/// Returns `None` if the versions differ in anything else.
+pub fn merge_usage(path: &Path, ours: &str, theirs: &str) -> Option<String> {
+    let ours_snippet = extract_snippet(path, ours)?;
+    let theirs_snippet = extract_snippet(path, theirs)?;
+
+    let used = ours_snippet.used.max(theirs_snippet.used).to_string();
+    let last_used = ours_snippet
+        .last_used
+        .max(theirs_snippet.last_used)
+        .map(|d| d.to_rfc3339());
+
+    let mut values = vec![("used", used.as_str())];
+    if let Some(last_used) = &last_used {
+        values.push(("last_used", last_used));
+    }
+
+    let merged = update_frontmatter(theirs, &values);
+    (update_frontmatter(ours, &values) == merged).then_some(merged)
+}
+
+/// Id in the frontmatter of the snippet file content
+pub fn read_id(content: &str) -> Option<String> {
+    extract_snippet(Path::new("snippet.md"), content)
+        .map(|s| s.id)
+        .filter(|id| !id.is_empty())
+}
+
+/// Set frontmatter keys of the markdown content, frontmatter is added if there isn't one
+fn update_frontmatter(content: &str, values: &[(&str, &str)]) -> String {
+    let mut lines = content.split_inclusive('\n');
+    let has_frontmatter = content.lines().next().map(str::trim_end) == Some("---");
+
+    let (mut frontmatter, body) = if has_frontmatter {
+        lines.next();
+        let mut frontmatter = String::new();
+        let mut body_start = None;
+        let mut offset = content.find('\n').map_or(content.len(), |i| i + 1);
+        for line in lines {
+            offset += line.len();
+            if line.trim_end() == "---" {
+                body_start = Some(offset);
+                break;
+            }
+            frontmatter.push_str(line);
+        }
+
+        match body_start {
+            Some(start) => (frontmatter, &content[start..]),
+            // unclosed frontmatter, treat the whole content as body
+            None => (String::new(), content),
+        }
+    } else {
+        (String::new(), content)
+    };
+
+    for (key, value) in values {
+        frontmatter = yaml_parser::set_yaml_value(&frontmatter, key, value);
+    }
+
+    if has_frontmatter {
+        format!("---\n{frontmatter}---\n{body}")
+    } else {
+        format!("---\n{frontmatter}---\n\n{body}")
+    }
+}
+
 fn extract_snippet(path: &Path, content: &str) -> Option<Snippet> {
     let mut start_offset = 0;
     let mut lines = content.split_inclusive('\n').map(|line| {
@@ -83,21 +1004,70 @@ fn extract_snippet(path: &Path, content: &str) -> Option<Snippet> {
     {
         let get_str = |k| yaml.get(k).and_then(|v| v.as_str());
 
+        if let Some(title) = get_str("title") {
+            snippet.title = title.to_string();
+        }
+        snippet.id = get_str("id").unwrap_or("").to_string();
         snippet.description = get_str("description").unwrap_or("").to_string();
+        snippet.alias = get_str("alias").map(String::from);
+        snippet.tags = match (yaml.get("tags").and_then(|t| t.as_vec()), get_str("tags")) {
+            (Some(tags), _) => tags.to_vec(),
+            (None, Some(inline)) => parse_inline_list(inline),
+            (None, None) => vec![],
+        };
         snippet.used = get_str("used").and_then(|s| s.parse().ok()).unwrap_or(0);
         snippet.last_used = get_str("last_used")
             .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
             .map(|d| d.with_timezone(&Local));
+        snippet.dangerous = get_str("dangerous") == Some("true");
+        snippet.cwd = get_str("cwd").map(String::from);
+        snippet.env = match (yaml.get("env").and_then(|e| e.as_map()), get_str("env")) {
+            (Some(env), _) => env.to_vec(),
+            (None, Some(inline)) => parse_inline_map(inline),
+            (None, None) => vec![],
+        };
     }
 
     Some(snippet)
 }
 
+/// Parse inline yaml map, e.g. `{RUST_LOG: debug, PORT: 8080}`
+fn parse_inline_map(value: &str) -> Vec<(String, String)> {
+    value
+        .trim_matches(['{', '}'])
+        .split(',')
+        .filter_map(|pair| pair.split_once(':'))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().trim_matches('"').to_string()))
+        .filter(|(k, _)| !k.is_empty())
+        .collect()
+}
+
+/// Parse inline yaml list, e.g. `[docker, cleanup]` or `docker, cleanup`
+pub fn parse_inline_list(value: &str) -> Vec<String> {
+    value
+        .trim_matches(['[', ']'])
+        .split(',')
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_dir::TestDir;
     use std::path::Path;
 
+    impl StateFiles {
+        fn in_dir(dir: &Path) -> Self {
+            StateFiles {
+                index: dir.join("index.tsv"),
+                ids: dir.join("ids.tsv"),
+                sidecar: dir.join("usage.tsv"),
+            }
+        }
+    }
+
     #[test]
     fn it_parses_valid_md() {
         let content = "---
@@ -150,6 +1120,26 @@ echo 'hello world'
         assert!(!result.command.contains("```"));
     }
 
+    #[test]
+    fn it_parses_cwd_and_env() {
+        let test_cases = vec![
+            "---\ncwd: ~/src/ono\nenv:\n  RUST_LOG: debug\n  PORT: 8080\n---\n```\ncargo run\n```\n",
+            "---\ncwd: ~/src/ono\nenv: {RUST_LOG: debug, PORT: \"8080\"}\n---\n```\ncargo run\n```\n",
+        ];
+
+        for content in test_cases {
+            let result = extract_snippet(Path::new("run.md"), content).unwrap();
+            assert_eq!(result.cwd.as_deref(), Some("~/src/ono"));
+            assert_eq!(
+                result.env,
+                vec![
+                    ("RUST_LOG".to_string(), "debug".to_string()),
+                    ("PORT".to_string(), "8080".to_string()),
+                ]
+            );
+        }
+    }
+
     #[test]
     fn it_parses_first_code_block() {
         let content = "```bash
@@ -171,6 +1161,463 @@ echo 'hello world 2'
         assert_eq!(result.used, 0);
     }
 
+    #[test]
+    fn it_updates_frontmatter() {
+        let test_cases = vec![
+            (
+                "---\ndescription: Test\nused: 1\n---\n```\nls\n```\n",
+                "---\ndescription: Test\nused: 2\n---\n```\nls\n```\n",
+            ),
+            ("```\nls\n```\n", "---\nused: 2\n---\n\n```\nls\n```\n"),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(update_frontmatter(input, &[("used", "2")]), expected);
+        }
+    }
+
+    #[test]
+    fn it_keeps_alias_of_higher_priority_snippet() {
+        let data_dirs = ["/notes", "/notes/team"].map(|path| DataDir {
+            path: path.to_string(),
+            label: None,
+            read_only: false,
+        });
+        // ranked, the team snippet comes first
+        let mut snippets: Vec<Snippet> = ["/notes/team/ls.md", "/notes/ls.md"]
+            .iter()
+            .map(|path| Snippet {
+                alias: Some("ls".to_string()),
+                path: PathBuf::from(path),
+                ..Default::default()
+            })
+            .collect();
+
+        resolve_alias_conflicts(&mut snippets, &data_dirs);
+
+        assert_eq!(snippets[0].alias, None);
+        assert_eq!(snippets[1].alias.as_deref(), Some("ls"));
+    }
+
+    #[test]
+    fn it_formats_new_snippets() {
+        let new_snippet = NewSnippet {
+            title: "list".to_string(),
+            description: "List files".to_string(),
+            alias: Some("ll".to_string()),
+            tags: vec!["files".to_string()],
+            command: "ls -al\n".to_string(),
+        };
+
+        let content = format_snippet(&new_snippet, "0123456789abcdef", None);
+        assert_eq!(
+            content,
+            "---\nid: 0123456789abcdef\ndescription: List files\nalias: ll\ntags:\n  - files\n---\n\n```\nls -al\n```\n"
+        );
+
+        let snippet = extract_snippet(Path::new("list.md"), &content).unwrap();
+        assert_eq!(snippet.id, "0123456789abcdef");
+        assert_eq!(snippet.command, "ls -al");
+        assert_eq!(snippet.alias.as_deref(), Some("ll"));
+        assert_eq!(snippet.tags, vec!["files"]);
+
+        let new_snippet = NewSnippet {
+            command: "df -h".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            format_snippet(&new_snippet, "0123456789abcdef", None),
+            "---\nid: 0123456789abcdef\n---\n\n```\ndf -h\n```\n"
+        );
+
+        let content = format_snippet(&new_snippet, "0123456789abcdef", Some("disk: usage"));
+        let snippet = extract_snippet(Path::new("disk- usage.md"), &content).unwrap();
+        assert_eq!(snippet.title, "disk: usage");
+    }
+
+    #[test]
+    fn it_parses_tags() {
+        let test_cases = vec![
+            ("tags:\n  - docker\n  - cleanup", vec!["docker", "cleanup"]),
+            ("tags: [docker, cleanup]", vec!["docker", "cleanup"]),
+            ("tags: docker", vec!["docker"]),
+            ("description: no tags", vec![]),
+        ];
+
+        for (frontmatter, expected) in test_cases {
+            let content = format!("---\n{frontmatter}\n---\n```\nls\n```\n");
+            let snippet = extract_snippet(Path::new("ls.md"), &content).unwrap();
+            assert_eq!(snippet.tags, expected);
+        }
+    }
+
+    #[test]
+    fn it_finds_duplicates() {
+        let snippets = vec![Snippet {
+            title: "List".to_string(),
+            alias: Some("ll".to_string()),
+            ..Default::default()
+        }];
+        let new_snippet = |title: &str, alias: Option<&str>| NewSnippet {
+            title: title.to_string(),
+            alias: alias.map(String::from),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            find_duplicate(&snippets, &new_snippet("list", None)),
+            Some(Duplicate::Title(_))
+        ));
+        assert!(matches!(
+            find_duplicate(&snippets, &new_snippet("other", Some("ll"))),
+            Some(Duplicate::Alias(_))
+        ));
+        assert!(find_duplicate(&snippets, &new_snippet("other", Some("ls"))).is_none());
+    }
+
+    #[test]
+    fn it_merges_usage_counters() {
+        let path = Path::new("ls.md");
+        let ours = "---\nused: 5\nlast_used: 2026-01-02T10:00:00+00:00\n---\n```\nls\n```\n";
+        let theirs = "---\nused: 3\nlast_used: 2026-03-01T10:00:00+00:00\n---\n```\nls\n```\n";
+
+        let merged = merge_usage(path, ours, theirs).unwrap();
+        let snippet = extract_snippet(path, &merged).unwrap();
+        assert_eq!(snippet.used, 5);
+        assert_eq!(
+            snippet.last_used.unwrap().to_rfc3339(),
+            DateTime::parse_from_rfc3339("2026-03-01T10:00:00+00:00")
+                .unwrap()
+                .with_timezone(&Local)
+                .to_rfc3339()
+        );
+
+        let edited = "---\nused: 3\n---\n```\nls -la\n```\n";
+        assert_eq!(merge_usage(path, ours, edited), None);
+    }
+
+    #[test]
+    fn it_merges_usage_into_externally_changed_file() {
+        let dir = TestDir::new("usage");
+        let path = dir.join("ls.md");
+        fs::write(&path, "---\nused: 5\n---\n```\nls\n```\n").unwrap();
+
+        let mut snippet = index::read_entry(&path).unwrap().snippet.unwrap();
+
+        // edited in another program while ono is open
+        fs::write(&path, "---\nused: 9\n---\nnotes\n```\nls -al\n```\n").unwrap();
+        record_usage(&mut snippet).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        let saved = extract_snippet(&path, &content).unwrap();
+        assert_eq!(saved.used, 10);
+        assert_eq!(saved.command, "ls -al");
+        assert!(content.contains("notes"));
+        assert_eq!(snippet.used, 10);
+    }
+
+    #[test]
+    fn it_reloads_changed_files() {
+        let dir = TestDir::new("reload");
+        let team_dir = dir.join("team");
+        fs::create_dir_all(&team_dir).unwrap();
+        let data_dirs = [&*dir, &*team_dir].map(|d| DataDir {
+            path: d.to_string_lossy().into_owned(),
+            label: None,
+            read_only: false,
+        });
+
+        fs::write(team_dir.join("team.md"), "```\nls\n```\n").unwrap();
+        let state = StateFiles::in_dir(&dir);
+        let mut snippets = load_snippets_with_state(&data_dirs, &state).snippets;
+
+        // added
+        let path = dir.join("mine.md");
+        fs::write(&path, "```\ndf -h\n```\n").unwrap();
+        reload_snippet_with_state(&mut snippets, &data_dirs, &path, &state).unwrap();
+        assert_eq!(snippets.len(), 2);
+        assert_eq!(snippets[1].title, "mine");
+
+        // changed
+        fs::write(&path, "```\ndf -hT\n```\n").unwrap();
+        reload_snippet_with_state(&mut snippets, &data_dirs, &path, &state).unwrap();
+        assert_eq!(snippets[1].command, "df -hT");
+
+        // deleted
+        fs::remove_file(&path).unwrap();
+        reload_snippet_with_state(&mut snippets, &data_dirs, &path, &state).unwrap();
+        assert_eq!(snippets.len(), 1);
+        assert_eq!(snippets[0].title, "team");
+    }
+
+    #[test]
+    fn it_keeps_aliases_of_higher_priority_snippets_on_reload() {
+        let dir = TestDir::new("reload-alias");
+        let team_dir = dir.join("team");
+        fs::create_dir_all(&team_dir).unwrap();
+        let data_dirs = [&*dir, &*team_dir].map(|d| DataDir {
+            path: d.to_string_lossy().into_owned(),
+            label: None,
+            read_only: false,
+        });
+
+        fs::write(dir.join("mine.md"), "---\nalias: l\n---\n```\nls\n```\n").unwrap();
+        let team_path = team_dir.join("team.md");
+        fs::write(&team_path, "```\nls -al\n```\n").unwrap();
+        let state = StateFiles::in_dir(&dir);
+        let mut snippets = load_snippets_with_state(&data_dirs, &state).snippets;
+        // ranked, the team snippet comes first
+        snippets.reverse();
+
+        fs::write(&team_path, "---\nalias: l\n---\n```\nls -al\n```\n").unwrap();
+        reload_snippet_with_state(&mut snippets, &data_dirs, &team_path, &state).unwrap();
+
+        let alias_of = |title: &str| {
+            let snippet = snippets.iter().find(|s| s.title == title).unwrap();
+            snippet.alias.clone()
+        };
+        assert_eq!(alias_of("mine").as_deref(), Some("l"));
+        assert_eq!(alias_of("team"), None);
+    }
+
+    #[test]
+    fn it_moves_snippets_and_rewrites_links() {
+        let dir = TestDir::new("move");
+        let team_dir = dir.join("team");
+        let git_dir = dir.join("git");
+        let docker_dir = dir.join("docker");
+        fs::create_dir_all(&git_dir).unwrap();
+        fs::create_dir_all(&team_dir).unwrap();
+        let data_dirs = [(&*dir, false), (&*team_dir, true)].map(|(d, read_only)| DataDir {
+            path: d.to_string_lossy().into_owned(),
+            label: None,
+            read_only,
+        });
+
+        let path = git_dir.join("prune.md");
+        fs::write(&path, "---\nid: abc\n---\n```\ndocker system prune\n```\n").unwrap();
+        fs::write(
+            git_dir.join("notes.md"),
+            "See [[prune]] and [[git/prune|this]]\n",
+        )
+        .unwrap();
+        let snippet = Snippet {
+            path: path.clone(),
+            ..Default::default()
+        };
+
+        // moved to a new folder of the data directory
+        let moved = move_snippet(&snippet, &data_dirs, &docker_dir, "docker: prune").unwrap();
+        assert_eq!(moved.snippet.path, docker_dir.join("docker- prune.md"));
+        assert_eq!(moved.snippet.title, "docker: prune");
+        assert_eq!(moved.snippet.id, "abc");
+        assert!(!path.exists());
+        assert_eq!(moved.updated_notes, vec![git_dir.join("notes.md")]);
+        assert_eq!(
+            fs::read_to_string(git_dir.join("notes.md")).unwrap(),
+            "See [[docker- prune]] and [[docker/docker- prune|this]]\n"
+        );
+
+        // the title key is updated even if the file name can represent the title
+        let renamed = move_snippet(&moved.snippet, &data_dirs, &docker_dir, "Prune").unwrap();
+        assert_eq!(renamed.snippet.path, docker_dir.join("Prune.md"));
+        assert_eq!(renamed.snippet.title, "Prune");
+
+        // only the case of the name changes
+        let renamed = move_snippet(&renamed.snippet, &data_dirs, &docker_dir, "prune").unwrap();
+        assert_eq!(renamed.snippet.title, "prune");
+        assert!(
+            fs::read_dir(&docker_dir)
+                .unwrap()
+                .any(|e| e.unwrap().file_name() == "prune.md")
+        );
+
+        let other_dir = TestDir::new("move-other");
+        let outside = other_dir.join("snippets");
+        assert!(move_snippet(&renamed.snippet, &data_dirs, &team_dir, "prune").is_err());
+        assert!(move_snippet(&renamed.snippet, &data_dirs, &outside, "prune").is_err());
+        assert!(move_snippet(&renamed.snippet, &data_dirs, &docker_dir, " ").is_err());
+        assert!(!outside.exists());
+    }
+
+    #[test]
+    fn it_leaves_links_when_moving_to_another_data_directory() {
+        let dir = TestDir::new("move-vault");
+        let personal_dir = dir.join("personal");
+        let work_dir = dir.join("work");
+        fs::create_dir_all(personal_dir.join("git")).unwrap();
+        fs::create_dir_all(work_dir.join("git")).unwrap();
+        let data_dirs = [&personal_dir, &work_dir].map(|d| DataDir {
+            path: d.to_string_lossy().into_owned(),
+            label: None,
+            read_only: false,
+        });
+
+        let path = personal_dir.join("git/prune.md");
+        fs::write(&path, "```\ngit prune\n```\n").unwrap();
+        let personal_note = personal_dir.join("notes.md");
+        fs::write(&personal_note, "See [[git/prune]]\n").unwrap();
+        // another note with the same path in the other vault
+        fs::write(work_dir.join("git/prune.md"), "```\ngit gc\n```\n").unwrap();
+        let work_note = work_dir.join("notes.md");
+        fs::write(&work_note, "See [[git/prune]]\n").unwrap();
+        let snippet = Snippet {
+            path: path.clone(),
+            ..Default::default()
+        };
+
+        let moved = move_snippet(&snippet, &data_dirs, &work_dir, "git prune").unwrap();
+        assert_eq!(moved.snippet.path, work_dir.join("git prune.md"));
+        assert!(moved.updated_notes.is_empty());
+        assert_eq!(moved.stale_notes, vec![personal_note.clone()]);
+        for note in [&personal_note, &work_note] {
+            assert_eq!(fs::read_to_string(note).unwrap(), "See [[git/prune]]\n");
+        }
+    }
+
+    #[test]
+    fn it_loads_snippets_in_folders() {
+        let dir = TestDir::new("folders");
+        let team_dir = dir.join("team");
+        for folder in ["docker/compose", ".obsidian", "team/git"] {
+            fs::create_dir_all(dir.join(folder)).unwrap();
+        }
+        for file in [
+            "ls.md",
+            "docker/ps.md",
+            "docker/compose/up.md",
+            ".obsidian/hidden.md",
+        ] {
+            fs::write(dir.join(file), "```\nls\n```\n").unwrap();
+        }
+        fs::write(team_dir.join("git/log.md"), "```\ngit log\n```\n").unwrap();
+        let data_dirs = [(&*dir, None), (&*team_dir, Some("team"))].map(|(d, label)| DataDir {
+            path: d.to_string_lossy().into_owned(),
+            label: label.map(String::from),
+            read_only: false,
+        });
+
+        let state = StateFiles::in_dir(&dir);
+        let snippets = load_snippets_with_state(&data_dirs, &state).snippets;
+        let titles: Vec<&str> = snippets.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, vec!["up", "ps", "ls", "log"]);
+        assert_eq!(
+            find_data_dir(&data_dirs, &team_dir.join("git/log.md"))
+                .unwrap()
+                .path,
+            data_dirs[1].path
+        );
+
+        let folders: Vec<String> = list_folders(&data_dirs)
+            .into_iter()
+            .map(|f| f.name)
+            .collect();
+        let dir_name = dir.to_string_lossy();
+        assert_eq!(
+            folders,
+            vec![
+                dir_name.to_string(),
+                format!("{dir_name}/docker"),
+                format!("{dir_name}/docker/compose"),
+                "team".to_string(),
+                "team/git".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_assigns_ids_and_keeps_them_when_files_move() {
+        let dir = TestDir::new("ids");
+        let team_dir = dir.join("team");
+        fs::create_dir_all(&team_dir).unwrap();
+        let data_dirs = [(&*dir, false), (&*team_dir, true)].map(|(d, read_only)| DataDir {
+            path: d.to_string_lossy().into_owned(),
+            label: None,
+            read_only,
+        });
+        let state = StateFiles::in_dir(&dir);
+
+        fs::write(dir.join("ls.md"), "```\nls\n```\n").unwrap();
+        fs::write(dir.join("ls copy.md"), "---\nid: abc\n---\n```\nls\n```\n").unwrap();
+        fs::write(dir.join("pwd.md"), "---\nid: abc\n---\n```\npwd\n```\n").unwrap();
+        fs::write(team_dir.join("df.md"), "```\ndf -h\n```\n").unwrap();
+        fs::write(team_dir.join("ls.md"), "```\nls\n```\n").unwrap();
+        let snippets = load_snippets_with_state(&data_dirs, &state).snippets;
+        let id_of = |snippets: &[Snippet], title: &str| {
+            snippets
+                .iter()
+                .find(|s| s.title == title)
+                .unwrap()
+                .id
+                .clone()
+        };
+
+        // a missing id is written to the file, a duplicate one is replaced
+        let ls_id = id_of(&snippets, "ls");
+        assert_eq!(
+            ls_id,
+            id::derive_id(&dir.join("ls.md"), "```\nls\n```\n", |_| false)
+        );
+        let content = fs::read_to_string(dir.join("ls.md")).unwrap();
+        assert_eq!(content, format!("---\nid: {ls_id}\n---\n\n```\nls\n```\n"));
+        assert_eq!(id_of(&snippets, "ls copy"), "abc");
+        assert_ne!(id_of(&snippets, "pwd"), "abc");
+        // the same file in another directory doesn't get the same id
+        let team_ls = snippets.iter().find(|s| s.path == team_dir.join("ls.md"));
+        assert_ne!(team_ls.unwrap().id, ls_id);
+
+        // read-only files keep the id in the state directory, even when moved
+        let df_id = id_of(&snippets, "df");
+        assert_eq!(
+            fs::read_to_string(team_dir.join("df.md")).unwrap(),
+            "```\ndf -h\n```\n"
+        );
+        fs::rename(team_dir.join("df.md"), team_dir.join("disk free.md")).unwrap();
+        fs::remove_file(&state.index).unwrap();
+        let snippets = load_snippets_with_state(&data_dirs, &state).snippets;
+        assert_eq!(id_of(&snippets, "disk free"), df_id);
+        assert_eq!(id_of(&snippets, "ls"), ls_id);
+        let ids = sidecar::load_ids(&state.ids);
+        assert_eq!(ids.len(), 2);
+        assert_eq!(ids[&team_dir.join("disk free.md")].id, df_id);
+    }
+
+    #[test]
+    fn it_loads_other_files_when_one_fails() {
+        let dir = TestDir::new("load");
+        let data_dirs = [DataDir {
+            path: dir.to_string_lossy().into_owned(),
+            label: None,
+            read_only: false,
+        }];
+
+        for i in 0..20 {
+            fs::write(
+                dir.join(format!("{i:02}.md")),
+                format!("```\necho {i}\n```\n"),
+            )
+            .unwrap();
+        }
+        let broken = dir.join("broken.md");
+        fs::write(&broken, [0xff, 0xfe, 0x00]).unwrap();
+
+        let state = StateFiles::in_dir(&dir);
+        let loaded = load_snippets_with_state(&data_dirs, &state);
+        assert_eq!(loaded.snippets.len(), 20);
+        assert_eq!(loaded.snippets[3].command, "echo 3");
+        assert_eq!(loaded.warnings.len(), 1);
+        assert_eq!(loaded.warnings[0].path, broken);
+    }
+
+    #[test]
+    fn it_parses_example_snippets() {
+        for (file_name, content) in EXAMPLE_SNIPPETS {
+            let snippet = extract_snippet(Path::new(file_name), content).unwrap();
+            assert!(!snippet.description.is_empty());
+            assert!(!snippet.command.is_empty());
+        }
+    }
+
     #[test]
     fn it_ignores_md_without_code_block() {
         let content = "---
@@ -0,0 +1,153 @@
+//! Writes that never leave a half written file behind and don't overwrite changes
+//! made by another program, e.g. Obsidian or another ono instance, since the file is read.
+
+use std::{
+    error::Error,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    process,
+    time::SystemTime,
+};
+
+use crate::store::id;
+
+/// State of a file when it's read, used to detect changes made by others
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    pub(super) modified: Option<SystemTime>,
+    pub(super) len: u64,
+    pub(super) hash: u64,
+}
+
+/// File is changed by another program since it's read
+#[derive(Debug)]
+pub struct ConflictError {
+    pub path: PathBuf,
+}
+
+impl fmt::Display for ConflictError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} was changed by another program", self.path.display())
+    }
+}
+
+impl Error for ConflictError {}
+
+pub fn is_conflict(err: &io::Error) -> bool {
+    err.get_ref().is_some_and(|e| e.is::<ConflictError>())
+}
+
+impl FileStamp {
+    fn new(metadata: &fs::Metadata, content: &[u8]) -> Self {
+        // stored in the index and compared to find moved files, so it has to be stable
+        FileStamp {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+            hash: id::stable_hash(content),
+        }
+    }
+}
+
+/// Read the file along with its stamp
+pub fn read_stamped(path: &Path) -> io::Result<(String, FileStamp)> {
+    let content = fs::read(path)?;
+    let stamp = FileStamp::new(&fs::metadata(path)?, &content);
+    let content = String::from_utf8(content).map_err(io::Error::other)?;
+    Ok((content, stamp))
+}
+
+fn current_stamp(path: &Path) -> io::Result<Option<FileStamp>> {
+    match fs::read(path) {
+        Ok(content) => Ok(Some(FileStamp::new(&fs::metadata(path)?, &content))),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Write the file through a temp file, replacing it at once
+pub fn write_atomic(path: &Path, content: &str) -> io::Result<FileStamp> {
+    write_checked(path, content, |_| true)
+}
+
+/// Write the file if it's still in the expected state, `None` means the file must not exist.
+/// Fails with `ConflictError` otherwise.
+pub fn write_if_unchanged(
+    path: &Path,
+    content: &str,
+    expected: Option<FileStamp>,
+) -> io::Result<FileStamp> {
+    write_checked(path, content, |current| current == expected)
+}
+
+fn write_checked(
+    path: &Path,
+    content: &str,
+    is_expected: impl Fn(Option<FileStamp>) -> bool,
+) -> io::Result<FileStamp> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        process::id()
+    ));
+
+    fs::write(&temp_path, content)?;
+    let result = fs::File::open(&temp_path)
+        .and_then(|f| f.sync_all())
+        .and_then(|_| {
+            // check as late as possible, right before replacing the file
+            if is_expected(current_stamp(path)?) {
+                fs::rename(&temp_path, path)
+            } else {
+                Err(io::Error::other(ConflictError {
+                    path: path.to_path_buf(),
+                }))
+            }
+        });
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result?;
+
+    Ok(FileStamp::new(&fs::metadata(path)?, content.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn it_writes_if_unchanged() {
+        let dir = TestDir::new("atomic-unchanged");
+        let path = dir.join("unchanged.md");
+        let stamp = write_if_unchanged(&path, "first", None).unwrap();
+
+        let (content, read_stamp) = read_stamped(&path).unwrap();
+        assert_eq!(content, "first");
+        assert_eq!(read_stamp, stamp);
+
+        write_if_unchanged(&path, "second", Some(stamp)).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+    }
+
+    #[test]
+    fn it_reports_conflicts() {
+        let dir = TestDir::new("atomic-conflict");
+        let path = dir.join("conflict.md");
+        let stamp = write_atomic(&path, "mine").unwrap();
+
+        // another program edits the file
+        fs::write(&path, "theirs").unwrap();
+
+        let err = write_if_unchanged(&path, "mine again", Some(stamp)).unwrap_err();
+        assert!(is_conflict(&err));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "theirs");
+
+        let err = write_if_unchanged(&path, "new file", None).unwrap_err();
+        assert!(is_conflict(&err));
+    }
+}
@@ -0,0 +1,258 @@
+//! Rules flagging commands that are hard to undo, e.g. `rm -rf` or `DROP TABLE`.
+//! A rule is a list of words the command must contain in order, within one
+//! command of a pipeline or list. Words are matched ignoring case except flags,
+//! `-r` and `-R` differ. `*` matches any characters and a short flag such as `-r`
+//! also matches `-rf` or `-fr`. A first word starting with `^` only matches the
+//! program being run, e.g. `^shutdown` matches `sudo shutdown` but not `grep shutdown`.
+
+use crate::store::Snippet;
+
+/// Rules every command is checked against, as pattern and reason
+static DEFAULT_RULES: [(&str, &str); 19] = [
+    ("rm -r", "deletes recursively"),
+    ("rm -R", "deletes recursively"),
+    ("rm --recursive", "deletes recursively"),
+    ("dd of=/dev/*", "overwrites a device"),
+    ("> /dev/sd*", "overwrites a device"),
+    ("mkfs*", "formats a file system"),
+    ("git push -f", "force pushes"),
+    ("git push --force", "force pushes"),
+    ("git reset --hard", "discards local changes"),
+    ("git clean -f", "deletes untracked files"),
+    ("chmod -R", "changes permissions recursively"),
+    ("chown -R", "changes ownership recursively"),
+    ("drop table", "drops a table"),
+    ("drop database", "drops a database"),
+    ("truncate table", "deletes every row of a table"),
+    ("kubectl delete", "deletes cluster resources"),
+    ("terraform destroy", "destroys infrastructure"),
+    ("docker system prune", "deletes docker data"),
+    ("^shutdown", "shuts the machine down"),
+];
+
+/// Words run before the program they're followed by, skipped to find the program
+static COMMAND_PREFIXES: [&str; 6] = ["sudo", "doas", "env", "nohup", "time", "exec"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub pattern: String,
+    pub reason: String,
+}
+
+impl Rule {
+    pub fn new(pattern: &str, reason: &str) -> Self {
+        Rule {
+            pattern: pattern.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+/// Reasons the command is dangerous, empty when no rule matches.
+/// The default rules are checked before the extra ones.
+pub fn analyse(command: &str, extra_rules: &[Rule]) -> Vec<String> {
+    let segments = split_commands(command);
+    let mut reasons: Vec<String> = vec![];
+
+    let rules = DEFAULT_RULES
+        .iter()
+        .map(|(pattern, reason)| (*pattern, *reason))
+        .chain(
+            extra_rules
+                .iter()
+                .map(|r| (r.pattern.as_str(), r.reason.as_str())),
+        );
+    for (pattern, reason) in rules {
+        let words: Vec<String> = pattern.split_whitespace().map(normalize_word).collect();
+        let is_match = !words.is_empty() && segments.iter().any(|s| matches_words(s, &words));
+        if is_match && !reasons.iter().any(|r| r == reason) {
+            reasons.push(reason.to_string());
+        }
+    }
+
+    reasons
+}
+
+/// Set the risks of every snippet from its command
+pub fn flag_snippets(snippets: &mut [Snippet], extra_rules: &[Rule]) {
+    for snippet in snippets {
+        snippet.risks = analyse(&snippet.command, extra_rules);
+    }
+}
+
+/// Words of each command, split on newlines, `;`, `&` and `|`, see `normalize_word`.
+/// Quotes and parentheses around words are dropped, so SQL in `psql -c "..."` is checked too.
+fn split_commands(command: &str) -> Vec<Vec<String>> {
+    command
+        .split(['\n', ';', '&', '|'])
+        .map(|segment| {
+            segment
+                .split_whitespace()
+                .map(|w| normalize_word(w.trim_matches(['"', '\'', '(', ')', '`'])))
+                .filter(|w| !w.is_empty())
+                .collect::<Vec<String>>()
+        })
+        .filter(|words| !words.is_empty())
+        .collect()
+}
+
+/// Lowercase the word unless it's a flag, as `-r` and `-R` often mean different things
+fn normalize_word(word: &str) -> String {
+    if word.starts_with('-') {
+        word.to_string()
+    } else {
+        word.to_lowercase()
+    }
+}
+
+/// Every pattern word matches a word of the command, in order.
+/// A first pattern word starting with `^` has to match the program being run.
+fn matches_words(command_words: &[String], pattern_words: &[String]) -> bool {
+    let mut words = command_words;
+    let mut patterns = pattern_words;
+    if let Some((first, rest)) = pattern_words.split_first()
+        && let Some(program) = first.strip_prefix('^')
+    {
+        let Some(idx) = program_position(command_words)
+            .filter(|&idx| matches_word(&command_words[idx], program))
+        else {
+            return false;
+        };
+        words = &command_words[idx + 1..];
+        patterns = rest;
+    }
+
+    let mut words = words.iter();
+    patterns
+        .iter()
+        .all(|pattern| words.any(|word| matches_word(word, pattern)))
+}
+
+/// Position of the program being run, after variable assignments, `sudo` and the like
+fn program_position(command_words: &[String]) -> Option<usize> {
+    let is_assignment = |word: &str| {
+        word.split_once('=').is_some_and(|(name, _)| {
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+    };
+
+    command_words.iter().position(|word| {
+        !word.starts_with('-') && !is_assignment(word) && !COMMAND_PREFIXES.contains(&&**word)
+    })
+}
+
+fn matches_word(word: &str, pattern: &str) -> bool {
+    // `-r` matches grouped short flags, `-rf`, but not long flags such as `--force`
+    if let Some(flag) = pattern.strip_prefix('-')
+        && flag.len() == 1
+        && let Some(flags) = word.strip_prefix('-')
+        && !flags.starts_with('-')
+    {
+        return flags.contains(flag);
+    }
+
+    matches_glob(word, pattern)
+}
+
+/// `*` matches any characters, everything else matches itself
+fn matches_glob(word: &str, pattern: &str) -> bool {
+    let Some((prefix, rest)) = pattern.split_once('*') else {
+        return word == pattern;
+    };
+    let Some(mut word) = word.strip_prefix(prefix) else {
+        return false;
+    };
+
+    let mut parts = rest.split('*').peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            return word.ends_with(part);
+        }
+        match word.find(part) {
+            Some(idx) => word = &word[idx + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_flags_dangerous_commands() {
+        let test_cases = vec![
+            ("ls -la", vec![]),
+            ("rm file.txt", vec![]),
+            ("rm -rf /tmp/build", vec!["deletes recursively"]),
+            ("sudo rm -Rf ./dist", vec!["deletes recursively"]),
+            ("rm --force file", vec![]),
+            ("git push --force-with-lease", vec![]),
+            ("git push origin main -f", vec!["force pushes"]),
+            (
+                "git fetch && git reset --hard origin/main",
+                vec!["discards local changes"],
+            ),
+            ("git reset --soft HEAD~1 && ls --hard", vec![]),
+            (
+                "sudo dd if=image.iso of=/dev/sdb bs=4M",
+                vec!["overwrites a device"],
+            ),
+            ("psql -c \"DROP TABLE users;\"", vec!["drops a table"]),
+            ("mkfs.ext4 /dev/sdb1", vec!["formats a file system"]),
+            ("echo drop; echo table", vec![]),
+            (
+                "chmod -R 755 public",
+                vec!["changes permissions recursively"],
+            ),
+            ("chmod -r secret.txt", vec![]),
+            ("sudo shutdown -h now", vec!["shuts the machine down"]),
+            (
+                "LANG=C nohup shutdown -r +5",
+                vec!["shuts the machine down"],
+            ),
+            ("grep shutdown /var/log/syslog", vec![]),
+            ("systemctl status shutdown.target", vec![]),
+        ];
+
+        for (command, expected) in test_cases {
+            assert_eq!(analyse(command, &[]), expected, "command: {command}");
+        }
+    }
+
+    #[test]
+    fn it_uses_extra_rules() {
+        let rules = vec![Rule::new("git push * main", "pushes to main")];
+
+        assert_eq!(
+            analyse("git push origin main", &rules),
+            vec!["pushes to main"]
+        );
+        assert!(analyse("git push origin feature", &rules).is_empty());
+
+        // flags are case sensitive
+        let rules = vec![Rule::new("git branch -D", "deletes unmerged branches")];
+        assert_eq!(
+            analyse("GIT branch -D feature", &rules),
+            vec!["deletes unmerged branches"]
+        );
+        assert!(analyse("git branch -d feature", &rules).is_empty());
+    }
+
+    #[test]
+    fn it_matches_globs() {
+        let test_cases = vec![
+            ("of=/dev/sda", "of=/dev/*", true),
+            ("of=disk.img", "of=/dev/*", false),
+            ("mkfs.ext4", "mkfs*", true),
+            ("terraform", "t*f*m", true),
+            ("tf", "t*f*m", false),
+            ("main", "main", true),
+        ];
+
+        for (word, pattern, expected) in test_cases {
+            assert_eq!(matches_glob(word, pattern), expected, "{word} {pattern}");
+        }
+    }
+}
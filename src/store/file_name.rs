@@ -0,0 +1,164 @@
+//! File names for new and renamed snippets.
+//! Titles are turned into names that are valid on every platform, so the data directory
+//! can be synced between machines.
+
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use unicode_normalization::UnicodeNormalization;
+
+/// Characters that aren't allowed in file names on at least one platform
+const ILLEGAL_CHARS: [char; 9] = ['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+/// Names Windows reserves for devices, with or without an extension
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Most file systems limit a name to 255 bytes, leave room for the extension and the counter
+const MAX_STEM_BYTES: usize = 200;
+
+const FALLBACK_STEM: &str = "snippet";
+
+/// Get the path for a new snippet in the data directory.
+/// Adds a counter, e.g. `title (2).md`, if the name is taken. Names are compared
+/// case insensitive as some file systems are.
+pub fn get_snippet_path(data_dir: &Path, title: &str) -> PathBuf {
+    data_dir.join(get_available_file_name(title, &list_file_names(data_dir)))
+}
+
+/// Get the path for a snippet renamed or moved to the folder.
+/// The current file of the snippet isn't taken, it can be renamed to itself.
+pub fn get_renamed_snippet_path(folder: &Path, title: &str, current: &Path) -> PathBuf {
+    let mut existing = list_file_names(folder);
+    if current.parent() == Some(folder)
+        && let Some(name) = current.file_name()
+    {
+        existing.remove(&name.to_string_lossy().to_lowercase());
+    }
+
+    folder.join(get_available_file_name(title, &existing))
+}
+
+/// Lowercase names of the files in the directory
+fn list_file_names(dir: &Path) -> HashSet<String> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.file_name().to_string_lossy().to_lowercase())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn get_available_file_name(title: &str, existing: &HashSet<String>) -> String {
+    let stem = to_file_stem(title);
+    let mut file_name = format!("{stem}.md");
+    let mut counter = 2;
+
+    while existing.contains(&file_name.to_lowercase()) {
+        file_name = format!("{stem} ({counter}).md");
+        counter += 1;
+    }
+
+    file_name
+}
+
+/// Convert the title to a file name without extension, valid on every platform
+pub fn to_file_stem(title: &str) -> String {
+    let normalized: String = title.nfc().collect();
+    let replaced: String = normalized
+        .chars()
+        .map(|c| {
+            if c.is_control() || ILLEGAL_CHARS.contains(&c) {
+                '-'
+            } else {
+                c
+            }
+        })
+        .collect();
+
+    // Windows doesn't allow trailing dots and spaces, leading dots hide the file on unix
+    let is_trimmed = |c: char| c == '.' || c.is_whitespace();
+    let mut stem = truncate(replaced.trim_matches(is_trimmed))
+        .trim_end_matches(is_trimmed)
+        .to_string();
+
+    if stem.is_empty() {
+        return FALLBACK_STEM.to_string();
+    }
+
+    let base_name = stem.split('.').next().unwrap_or_default();
+    if RESERVED_NAMES
+        .iter()
+        .any(|r| r.eq_ignore_ascii_case(base_name))
+    {
+        stem.insert(base_name.len(), '_');
+    }
+
+    stem
+}
+
+/// Cut the text to the stem length limit without splitting a character
+fn truncate(text: &str) -> &str {
+    if text.len() <= MAX_STEM_BYTES {
+        return text;
+    }
+
+    let mut end = MAX_STEM_BYTES;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_converts_titles_to_file_stems() {
+        let test_cases = vec![
+            ("List directory", "List directory"),
+            ("docker: prune <all>", "docker- prune -all-"),
+            ("a/b\\c|d?e*f\"g", "a-b-c-d-e-f-g"),
+            ("tab\there", "tab-here"),
+            ("  trailing dots...  ", "trailing dots"),
+            (".hidden", "hidden"),
+            ("CON", "CON_"),
+            ("nul.txt", "nul_.txt"),
+            ("Console", "Console"),
+            ("...", FALLBACK_STEM),
+            ("", FALLBACK_STEM),
+            // decomposed é is composed
+            ("cafe\u{301}", "caf\u{e9}"),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(to_file_stem(input), expected, "input: {input:?}");
+        }
+    }
+
+    #[test]
+    fn it_limits_file_stem_length() {
+        let stem = to_file_stem(&"ü".repeat(150));
+        assert!(stem.len() <= MAX_STEM_BYTES);
+        assert!(stem.chars().all(|c| c == 'ü'));
+    }
+
+    #[test]
+    fn it_adds_counter_on_collision() {
+        let existing: HashSet<String> = ["list.md", "list (2).md"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        assert_eq!(get_available_file_name("List", &existing), "List (3).md");
+        assert_eq!(get_available_file_name("Other", &existing), "Other.md");
+    }
+}
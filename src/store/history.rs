@@ -0,0 +1,269 @@
+//! Local log of snippet uses, kept in the state directory instead of the snippet files.
+//! Each use is appended as a line with where it happened, how the command exited and
+//! the placeholder values, for ranking, `ono stats` and recalling values.
+//! Uses are recorded by snippet id so they follow the snippet when its file is renamed.
+
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local};
+
+use crate::{
+    os_helper,
+    store::{
+        Snippet,
+        index::{escape, unescape},
+    },
+};
+
+static HISTORY_FILE: &str = "history.tsv";
+
+/// A use of a snippet
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub time: DateTime<Local>,
+    /// Id of the snippet
+    pub id: String,
+    pub path: PathBuf,
+    /// Directory ono is run in
+    pub cwd: Option<PathBuf>,
+    /// Root of the git repository the directory is in
+    pub git_root: Option<PathBuf>,
+    /// Exit code of the command, when it's run by ono
+    pub exit_status: Option<i32>,
+    /// Placeholder names and the values entered for them
+    pub variables: Vec<(String, String)>,
+}
+
+impl Record {
+    /// Use of the snippet now, in the current directory
+    pub fn new(snippet: &Snippet) -> Self {
+        let cwd = env::current_dir().ok();
+        Record {
+            time: Local::now(),
+            id: snippet.id.clone(),
+            path: snippet.path.clone(),
+            git_root: cwd.as_deref().and_then(find_git_root),
+            cwd,
+            exit_status: None,
+            variables: vec![],
+        }
+    }
+
+    /// Whether it's a use of the snippet
+    pub fn is_for(&self, snippet: &Snippet) -> bool {
+        self.id == snippet.id
+    }
+}
+
+pub fn get_history_path() -> PathBuf {
+    os_helper::get_state_dir().join(HISTORY_FILE)
+}
+
+/// Load every use in the order they're recorded, missing file means nothing is used yet
+pub fn load(path: &Path) -> Vec<Record> {
+    fs::read_to_string(path)
+        .map(|content| content.lines().filter_map(parse_record).collect())
+        .unwrap_or_default()
+}
+
+/// Append the use to the history, the line is written at once so concurrent writers don't mix
+pub fn append(path: &Path, record: &Record) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(format_record(record).as_bytes())
+}
+
+/// Distinct values entered for the placeholder in the snippet, or in any snippet
+/// without snippet, most recent first
+pub fn recent_values<'a>(
+    history: &'a [Record],
+    snippet: Option<&Snippet>,
+    name: &str,
+    limit: usize,
+) -> Vec<&'a str> {
+    let mut result: Vec<&str> = vec![];
+    let values = history
+        .iter()
+        .rev()
+        .filter(|r| snippet.is_none_or(|s| r.is_for(s)))
+        .flat_map(|r| r.variables.iter().rev())
+        .filter(|(n, _)| n == name);
+
+    for (_, value) in values {
+        if result.len() == limit {
+            break;
+        }
+        if !result.contains(&value.as_str()) {
+            result.push(value);
+        }
+    }
+    result
+}
+
+/// The directory or its closest parent with a `.git` directory or file
+pub fn find_git_root(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .find(|d| d.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+fn parse_record(line: &str) -> Option<Record> {
+    let fields: Vec<String> = line.split('\t').map(unescape).collect();
+    let optional_path = |value: &String| (!value.is_empty()).then(|| PathBuf::from(value));
+
+    Some(Record {
+        time: DateTime::parse_from_rfc3339(fields.first()?)
+            .ok()?
+            .with_timezone(&Local),
+        path: PathBuf::from(fields.get(1)?),
+        cwd: fields.get(2).and_then(optional_path),
+        git_root: fields.get(3).and_then(optional_path),
+        exit_status: fields.get(4)?.parse().ok(),
+        variables: fields
+            .get(5)?
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(name, value)| (name.to_string(), unescape(value)))
+            .collect(),
+        id: fields.get(6)?.clone(),
+    })
+}
+
+fn format_record(record: &Record) -> String {
+    let optional_path = |path: &Option<PathBuf>| {
+        path.as_deref()
+            .map(|p| escape(&p.to_string_lossy()))
+            .unwrap_or_default()
+    };
+
+    // values are escaped on their own so they can't break the lines of the field
+    let variables: Vec<String> = record
+        .variables
+        .iter()
+        .map(|(name, value)| format!("{name}={}", escape(value)))
+        .collect();
+
+    format!(
+        "{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+        record.time.to_rfc3339(),
+        escape(&record.path.to_string_lossy()),
+        optional_path(&record.cwd),
+        optional_path(&record.git_root),
+        record
+            .exit_status
+            .map(|s| s.to_string())
+            .unwrap_or_default(),
+        escape(&variables.join("\n")),
+        escape(&record.id),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn it_appends_and_loads_records() {
+        let dir = TestDir::new("history");
+        let path = dir.join(HISTORY_FILE);
+
+        let records = vec![
+            Record {
+                time: Local::now(),
+                id: "0123456789abcdef".to_string(),
+                path: PathBuf::from("/data/list.md"),
+                cwd: Some(PathBuf::from("/src/ono/src")),
+                git_root: Some(PathBuf::from("/src/ono")),
+                exit_status: Some(2),
+                variables: vec![
+                    ("host".to_string(), "example.com".to_string()),
+                    ("query".to_string(), "a=b\nc\td".to_string()),
+                ],
+            },
+            Record {
+                time: Local::now(),
+                id: "fedcba9876543210".to_string(),
+                path: PathBuf::from("/data/tab\there.md"),
+                cwd: None,
+                git_root: None,
+                exit_status: None,
+                variables: vec![],
+            },
+        ];
+        for record in &records {
+            append(&path, record).unwrap();
+        }
+        let content = fs::read_to_string(&path).unwrap() + "not a record\n";
+        fs::write(&path, content).unwrap();
+
+        let loaded = load(&path);
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].id, records[0].id);
+        assert_eq!(loaded[0].path, records[0].path);
+        assert_eq!(loaded[0].git_root, records[0].git_root);
+        assert_eq!(loaded[0].exit_status, Some(2));
+        assert_eq!(loaded[0].variables, records[0].variables);
+        assert_eq!(loaded[1].id, records[1].id);
+        assert_eq!(loaded[1].path, records[1].path);
+        assert_eq!(loaded[1].cwd, None);
+    }
+
+    fn snippet(id: &str, path: &str) -> Snippet {
+        Snippet {
+            id: id.to_string(),
+            path: PathBuf::from(path),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn it_gets_recent_values() {
+        let record = |id: &str, path: &str, variables: &[(&str, &str)]| Record {
+            variables: variables
+                .iter()
+                .map(|(n, v)| (n.to_string(), v.to_string()))
+                .collect(),
+            ..Record::new(&snippet(id, path))
+        };
+        let history = vec![
+            record("ping", "/data/ping.md", &[("host", "a.example.com")]),
+            record("ping", "/data/ping.md", &[("host", "c.example.com")]),
+            record("ssh", "/data/ssh.md", &[("host", "b.example.com")]),
+            record("ping", "/data/ping.md", &[("host", "a.example.com")]),
+            record("ssh", "/data/ssh.md", &[("user", "root")]),
+        ];
+
+        let ping = snippet("ping", "/data/network/ping.md");
+        let ping = Some(&ping);
+        assert_eq!(
+            recent_values(&history, ping, "host", 5),
+            vec!["a.example.com", "c.example.com"]
+        );
+        assert_eq!(
+            recent_values(&history, None, "host", 2),
+            vec!["a.example.com", "b.example.com"]
+        );
+        assert!(recent_values(&history, ping, "user", 5).is_empty());
+        assert_eq!(recent_values(&history, None, "user", 5), vec!["root"]);
+    }
+
+    #[test]
+    fn it_finds_git_root() {
+        let dir = TestDir::new("git-root");
+        let nested = dir.join("src/store");
+        fs::create_dir_all(&nested).unwrap();
+        fs::create_dir_all(dir.join(".git")).unwrap();
+
+        assert_eq!(find_git_root(&nested), Some(dir.to_path_buf()));
+        assert_eq!(find_git_root(&dir), Some(dir.to_path_buf()));
+    }
+}
@@ -0,0 +1,81 @@
+//! Snippet ids, kept in the `id` frontmatter key so uses and aliases follow a snippet
+//! when its file is renamed or moved.
+
+use std::{
+    hash::{BuildHasher, RandomState},
+    path::Path,
+    time::SystemTime,
+};
+
+/// Id for a file without one, from its name and content rather than random so machines
+/// syncing the same file give it the same id and their changes don't conflict.
+/// When the id is taken, e.g. by the same file in another data directory, it's salted
+/// with a counter until it's free.
+pub fn derive_id(path: &Path, content: &str, is_taken: impl Fn(&str) -> bool) -> String {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let hash = fnv1a(fnv1a(FNV_OFFSET, name.as_bytes()), content.as_bytes());
+
+    (0u32..)
+        .map(|salt| match salt {
+            0 => hash,
+            _ => fnv1a(hash, &salt.to_le_bytes()),
+        })
+        .map(|hash| format!("{hash:016x}"))
+        .find(|id| !is_taken(id))
+        .unwrap_or_else(new_id)
+}
+
+/// Random id for a snippet created by ono
+pub fn new_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    format!("{:016x}", RandomState::new().hash_one(nanos))
+}
+
+/// Hash that stays the same across Rust versions and machines, for hashes that are stored
+pub(super) fn stable_hash(bytes: &[u8]) -> u64 {
+    fnv1a(FNV_OFFSET, bytes)
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// FNV-1a, the std hashers don't promise the same result across Rust versions
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_derives_ids() {
+        let derive = |path: &str, content: &str| derive_id(Path::new(path), content, |_| false);
+        let id = derive("/data/ls.md", "```\nls\n```\n");
+        assert_eq!(id.len(), 16);
+        assert_eq!(id, derive("/other/ls.md", "```\nls\n```\n"));
+        assert_ne!(id, derive("/data/ls copy.md", "```\nls\n```\n"));
+        assert_ne!(id, derive("/data/ls.md", "```\nls -l\n```\n"));
+        assert_ne!(new_id(), new_id());
+    }
+
+    #[test]
+    fn it_salts_taken_ids() {
+        let path = Path::new("/team/ls.md");
+        let content = "```\nls\n```\n";
+        let first = derive_id(path, content, |_| false);
+        let second = derive_id(path, content, |id| id == first);
+        let third = derive_id(path, content, |id| id == first || id == second);
+
+        assert_ne!(first, second);
+        assert_ne!(second, third);
+        assert_ne!(first, third);
+        assert_eq!(second, derive_id(path, content, |id| id == first));
+    }
+}
@@ -0,0 +1,434 @@
+//! Parsed snippets cached on disk, so only the files changed since the last start are read.
+//! Entries are keyed by path and checked against the file's modification time and size.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use chrono::{DateTime, Local};
+
+use crate::{
+    os_helper,
+    store::{Snippet, atomic_file, atomic_file::FileStamp, extract_snippet},
+};
+
+static INDEX_FILE: &str = "index.tsv";
+
+/// First line of the index, the version is bumped when the format changes
+const HEADER: &str = "ono-index\t5";
+
+#[derive(Debug, Default)]
+pub struct Index {
+    entries: HashMap<PathBuf, Entry>,
+    /// Paths looked up since the index is loaded, the others are dropped on save
+    seen: HashSet<PathBuf>,
+    changed: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    stamp: FileStamp,
+    /// `None` for markdown files that aren't snippets, they aren't read again either
+    pub snippet: Option<Snippet>,
+}
+
+pub fn get_index_path() -> PathBuf {
+    os_helper::get_cache_dir().join(INDEX_FILE)
+}
+
+/// Remove the index, every file is parsed again on the next load
+pub fn remove_index(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+impl Index {
+    /// Load the index, a missing or outdated index is treated as empty
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .map(|content| parse_index(&content))
+            .unwrap_or_default()
+    }
+
+    /// Write the index if it's changed, dropping the files that weren't looked up
+    pub fn save(&mut self, path: &Path) -> io::Result<()> {
+        let before = self.entries.len();
+        self.entries.retain(|path, _| self.seen.contains(path));
+        if !self.changed && self.entries.len() == before {
+            return Ok(());
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        atomic_file::write_atomic(path, &format_index(&self.entries))?;
+        self.changed = false;
+        Ok(())
+    }
+
+    /// Get the indexed snippet of the file if the file isn't changed since it's indexed.
+    /// `Some(None)` means the file is indexed but isn't a snippet.
+    pub fn get(&mut self, path: &Path) -> Option<Option<Snippet>> {
+        self.seen.insert(path.to_path_buf());
+
+        let metadata = fs::metadata(path).ok()?;
+        self.entries
+            .get(path)
+            .filter(|entry| {
+                entry.stamp.modified == metadata.modified().ok()
+                    && entry.stamp.len == metadata.len()
+            })
+            .map(|entry| entry.snippet.clone())
+    }
+
+    /// Index the file, a changed file without `id` keeps the id it's indexed with.
+    /// Returns the indexed snippet.
+    pub fn insert(&mut self, path: &Path, mut entry: Entry) -> Option<Snippet> {
+        self.seen.insert(path.to_path_buf());
+        let indexed_id = self
+            .entries
+            .get(path)
+            .and_then(|e| e.snippet.as_ref())
+            .map(|s| s.id.clone());
+        if let (Some(snippet), Some(id)) = (&mut entry.snippet, indexed_id)
+            && snippet.id.is_empty()
+        {
+            snippet.id = id;
+        }
+
+        let snippet = entry.snippet.clone();
+        self.entries.insert(path.to_path_buf(), entry);
+        self.changed = true;
+        snippet
+    }
+
+    /// Set the id given to the indexed snippet, along with the stamp if the id is written
+    /// to the file
+    pub fn set_id(&mut self, path: &Path, id: &str, stamp: Option<FileStamp>) {
+        if let Some(entry) = self.entries.get_mut(path) {
+            if let Some(stamp) = stamp {
+                entry.stamp = stamp;
+            }
+            if let Some(snippet) = &mut entry.snippet {
+                snippet.id = id.to_string();
+                snippet.stamp = Some(entry.stamp);
+            }
+            self.changed = true;
+        }
+    }
+
+    /// Ids of the indexed snippets whose file is gone, keyed by the content hash of the file,
+    /// a new file with the same content is the file moved
+    pub fn removed_ids(&self) -> HashMap<u64, String> {
+        self.entries
+            .iter()
+            .filter(|(path, _)| !self.seen.contains(*path) && !path.exists())
+            .filter_map(|(_, entry)| Some((entry.stamp.hash, entry.snippet.as_ref()?.id.clone())))
+            .filter(|(_, id)| !id.is_empty())
+            .collect()
+    }
+}
+
+/// Read and parse the file for the index
+pub fn read_entry(path: &Path) -> io::Result<Entry> {
+    let (content, stamp) = atomic_file::read_stamped(path)?;
+    let snippet = extract_snippet(path, &content).map(|mut snippet| {
+        snippet.stamp = Some(stamp);
+        snippet
+    });
+
+    Ok(Entry { stamp, snippet })
+}
+
+fn parse_index(content: &str) -> Index {
+    let mut lines = content.lines();
+    if lines.next() != Some(HEADER) {
+        log::info!("Snippet index is outdated, rebuilding it");
+        return Index::default();
+    }
+
+    Index {
+        entries: lines.filter_map(parse_entry).collect(),
+        ..Default::default()
+    }
+}
+
+/// One file per line: path, stamp and the snippet fields if it's a snippet
+fn parse_entry(line: &str) -> Option<(PathBuf, Entry)> {
+    let fields: Vec<String> = line.split('\t').map(unescape).collect();
+    let path = PathBuf::from(fields.first()?);
+    let modified = match fields.get(1)?.as_str() {
+        "" => None,
+        nanos => Some(UNIX_EPOCH + Duration::from_nanos(nanos.parse().ok()?)),
+    };
+    let stamp = FileStamp {
+        modified,
+        len: fields.get(2)?.parse().ok()?,
+        hash: fields.get(3)?.parse().ok()?,
+    };
+
+    let snippet = match &fields[4..] {
+        [] => None,
+        [
+            title,
+            description,
+            command,
+            alias,
+            tags,
+            used,
+            last_used,
+            dangerous,
+            cwd,
+            env,
+            id,
+        ] => Some(Snippet {
+            title: title.clone(),
+            description: description.clone(),
+            command: command.clone(),
+            alias: (!alias.is_empty()).then(|| alias.clone()),
+            tags: tags.lines().map(String::from).collect(),
+            path: path.clone(),
+            used: used.parse().ok()?,
+            last_used: DateTime::parse_from_rfc3339(last_used)
+                .ok()
+                .map(|d| d.with_timezone(&Local)),
+            dangerous: dangerous == "true",
+            cwd: (!cwd.is_empty()).then(|| cwd.clone()),
+            env: env
+                .lines()
+                .filter_map(|line| line.split_once('='))
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            id: id.clone(),
+            stamp: Some(stamp),
+            ..Default::default()
+        }),
+        _ => return None,
+    };
+
+    Some((path, Entry { stamp, snippet }))
+}
+
+fn format_index(entries: &HashMap<PathBuf, Entry>) -> String {
+    let mut lines: Vec<String> = entries
+        .iter()
+        .map(|(path, entry)| format_entry(path, entry))
+        .collect();
+    lines.sort();
+
+    let mut content = format!("{HEADER}\n");
+    for line in lines {
+        content.push_str(&line);
+        content.push('\n');
+    }
+    content
+}
+
+fn format_entry(path: &Path, entry: &Entry) -> String {
+    let modified = entry
+        .stamp
+        .modified
+        .and_then(|m| m.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos().to_string())
+        .unwrap_or_default();
+    let mut fields = vec![
+        path.to_string_lossy().to_string(),
+        modified,
+        entry.stamp.len.to_string(),
+        entry.stamp.hash.to_string(),
+    ];
+
+    if let Some(snippet) = &entry.snippet {
+        fields.extend([
+            snippet.title.clone(),
+            snippet.description.clone(),
+            snippet.command.clone(),
+            snippet.alias.clone().unwrap_or_default(),
+            snippet.tags.join("\n"),
+            snippet.used.to_string(),
+            snippet
+                .last_used
+                .map(|d| d.to_rfc3339())
+                .unwrap_or_default(),
+            snippet.dangerous.to_string(),
+            snippet.cwd.clone().unwrap_or_default(),
+            snippet
+                .env
+                .iter()
+                .map(|(k, v)| format!("{k}={v}"))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            snippet.id.clone(),
+        ]);
+    }
+
+    fields
+        .iter()
+        .map(|f| escape(f))
+        .collect::<Vec<_>>()
+        .join("\t")
+}
+
+/// Escape the characters that separate fields and entries
+pub(super) fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+pub(super) fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    fn get_snippet(index: &mut Index, path: &Path) -> Option<Snippet> {
+        index.get(path).unwrap_or_else(|| {
+            let entry = read_entry(path).unwrap();
+            index.insert(path, entry)
+        })
+    }
+
+    #[test]
+    fn it_escapes_fields() {
+        let test_cases = vec!["plain", "tab\there", "two\nlines\r\n", "back\\slash\\t"];
+
+        for input in test_cases {
+            assert_eq!(unescape(&escape(input)), input, "input: {input:?}");
+            assert!(!escape(input).contains(['\t', '\n']));
+        }
+    }
+
+    #[test]
+    fn it_round_trips_entries() {
+        let dir = TestDir::new("index-round-trip");
+        let path = dir.join("prune.md");
+        fs::write(
+            &path,
+            "---\nid: 1a2b3c4d5e6f7a8b\nalias: dp\ndangerous: true\ncwd: /tmp\nenv: {DOCKER_HOST: unix:///run/docker.sock}\ntags: [docker, cleanup]\nused: 2\n---\n\n```bash\ndocker system prune\n\tdocker image prune\n```\n",
+        )
+        .unwrap();
+        let readme = dir.join("README.md");
+        fs::write(&readme, "no code here").unwrap();
+
+        let index_path = dir.join(INDEX_FILE);
+        let mut index = Index::default();
+        let snippet = get_snippet(&mut index, &path).unwrap();
+        assert!(get_snippet(&mut index, &readme).is_none());
+        index.save(&index_path).unwrap();
+
+        let mut loaded = Index::load(&index_path);
+        assert_eq!(loaded.entries.len(), 2);
+        assert!(loaded.entries[&readme].snippet.is_none());
+
+        let cached = get_snippet(&mut loaded, &path).unwrap();
+        assert!(!loaded.changed);
+        assert_eq!(cached.title, snippet.title);
+        assert_eq!(cached.command, snippet.command);
+        assert_eq!(cached.id, "1a2b3c4d5e6f7a8b");
+        assert_eq!(cached.alias.as_deref(), Some("dp"));
+        assert_eq!(cached.tags, vec!["docker", "cleanup"]);
+        assert_eq!(cached.used, 2);
+        assert!(cached.dangerous);
+        assert_eq!(cached.cwd.as_deref(), Some("/tmp"));
+        assert_eq!(
+            cached.env,
+            vec![(
+                "DOCKER_HOST".to_string(),
+                "unix:///run/docker.sock".to_string()
+            )]
+        );
+        assert_eq!(cached.stamp, snippet.stamp);
+    }
+
+    #[test]
+    fn it_refreshes_changed_and_removed_files() {
+        let dir = TestDir::new("index-refresh");
+        let kept = dir.join("kept.md");
+        let removed = dir.join("removed.md");
+        fs::write(&kept, "```bash\nls\n```\n").unwrap();
+        fs::write(&removed, "```bash\npwd\n```\n").unwrap();
+
+        let index_path = dir.join(INDEX_FILE);
+        let mut index = Index::default();
+        get_snippet(&mut index, &kept);
+        get_snippet(&mut index, &removed);
+        index.save(&index_path).unwrap();
+
+        fs::write(&kept, "```bash\nls -la\n```\n").unwrap();
+        fs::remove_file(&removed).unwrap();
+
+        let mut index = Index::load(&index_path);
+        let snippet = get_snippet(&mut index, &kept).unwrap();
+        assert_eq!(snippet.command, "ls -la");
+        index.save(&index_path).unwrap();
+
+        let index = Index::load(&index_path);
+        assert_eq!(index.entries.keys().collect::<Vec<_>>(), vec![&kept]);
+    }
+
+    #[test]
+    fn it_keeps_ids_of_changed_and_moved_files() {
+        let dir = TestDir::new("index-ids");
+        let changed = dir.join("changed.md");
+        let moved = dir.join("moved.md");
+        fs::write(&changed, "```bash\nls\n```\n").unwrap();
+        fs::write(&moved, "```bash\npwd\n```\n").unwrap();
+
+        let index_path = dir.join(INDEX_FILE);
+        let mut index = Index::default();
+        get_snippet(&mut index, &changed);
+        get_snippet(&mut index, &moved);
+        index.set_id(&changed, "aaaa", None);
+        index.set_id(&moved, "bbbb", None);
+        index.save(&index_path).unwrap();
+
+        fs::write(&changed, "```bash\nls -la\n```\n").unwrap();
+        let new_path = dir.join("renamed.md");
+        fs::rename(&moved, &new_path).unwrap();
+
+        let mut index = Index::load(&index_path);
+        assert_eq!(get_snippet(&mut index, &changed).unwrap().id, "aaaa");
+        let new_snippet = get_snippet(&mut index, &new_path).unwrap();
+        assert!(new_snippet.id.is_empty());
+        let removed_ids = index.removed_ids();
+        assert_eq!(
+            removed_ids
+                .get(&new_snippet.stamp.unwrap().hash)
+                .map(String::as_str),
+            Some("bbbb")
+        );
+    }
+
+    #[test]
+    fn it_ignores_outdated_index() {
+        let index = parse_index("ono-index\t1\n/a.md\t1\t2\t3\n");
+        assert!(index.entries.is_empty());
+    }
+}
@@ -0,0 +1,126 @@
+//! Placeholders in commands, `<name>` or `<name=default>`, filled in before running.
+//! The syntax is the one navi and pet use, so imported snippets keep working.
+
+use std::collections::HashMap;
+
+#[derive(Debug, PartialEq)]
+pub struct Placeholder {
+    pub name: String,
+    pub default: Option<String>,
+}
+
+/// Placeholders of the command in order of appearance, each name once
+pub fn find_placeholders(command: &str) -> Vec<Placeholder> {
+    let mut result: Vec<Placeholder> = vec![];
+    for (_, name, default) in parse(command) {
+        if !result.iter().any(|p| p.name == name) {
+            result.push(Placeholder {
+                name: name.to_string(),
+                default: default.map(String::from),
+            });
+        }
+    }
+    result
+}
+
+/// Replace the placeholders with the values, placeholders without a value are kept
+pub fn substitute(command: &str, values: &HashMap<String, String>) -> String {
+    let mut result = String::with_capacity(command.len());
+    let mut end = 0;
+
+    for ((start, len), name, _) in parse(command) {
+        if let Some(value) = values.get(name) {
+            result.push_str(&command[end..start]);
+            result.push_str(value);
+            end = start + len;
+        }
+    }
+
+    result.push_str(&command[end..]);
+    result
+}
+
+/// Position and length, name and default value of each placeholder.
+/// Names are letters, digits and `_-./`, so redirections such as `< file` and
+/// heredocs such as `<<EOF` aren't mistaken for placeholders.
+fn parse(command: &str) -> Vec<((usize, usize), &str, Option<&str>)> {
+    let mut result = vec![];
+    let mut offset = 0;
+
+    while let Some(start) = command[offset..].find('<').map(|i| i + offset) {
+        offset = start + 1;
+        let Some(len) = command[start..].find('>') else {
+            break;
+        };
+
+        let inner = &command[start + 1..start + len];
+        let (name, default) = match inner.split_once('=') {
+            Some((name, default)) => (name, Some(default)),
+            None => (inner, None),
+        };
+
+        let is_name = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '/'));
+        let is_default = default.is_none_or(|d| !d.contains(['<', '\n']));
+        if is_name && is_default {
+            result.push(((start, len + 1), name, default));
+            offset = start + len + 1;
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_placeholders() {
+        let test_cases = vec![
+            ("ls -la", vec![]),
+            ("git checkout <branch>", vec![("branch", None)]),
+            (
+                "ping <host=8.8.8.8> -c <count=3> && echo <host>",
+                vec![("host", Some("8.8.8.8")), ("count", Some("3"))],
+            ),
+            ("sort < input.txt > out.txt", vec![]),
+            ("cat <<EOF\n<name>\nEOF", vec![("name", None)]),
+            ("diff <(ls a) <(ls b)", vec![]),
+            // tldr style placeholders
+            (
+                "tar cf <path/to/target.tar>",
+                vec![("path/to/target.tar", None)],
+            ),
+        ];
+
+        for (input, expected) in test_cases {
+            let expected: Vec<Placeholder> = expected
+                .into_iter()
+                .map(|(name, default)| Placeholder {
+                    name: name.to_string(),
+                    default: default.map(String::from),
+                })
+                .collect();
+            assert_eq!(find_placeholders(input), expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn it_substitutes_placeholders() {
+        let values = HashMap::from([
+            ("host".to_string(), "example.com".to_string()),
+            ("count".to_string(), "1".to_string()),
+        ]);
+
+        assert_eq!(
+            substitute(
+                "ping <host=8.8.8.8> -c <count> <other> && echo <host>",
+                &values
+            ),
+            "ping example.com -c 1 <other> && echo example.com"
+        );
+    }
+}
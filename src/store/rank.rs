@@ -0,0 +1,136 @@
+//! Order of the snippet list. Snippets used in the current directory or project
+//! float to the top, the rest are ordered by how often and how recently they're used.
+//! Uses come from the local history, `used` and `last_used` of the files only break ties,
+//! they include uses on other machines but not when or where.
+
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::HashMap,
+    env,
+    path::PathBuf,
+};
+
+use chrono::{DateTime, Local};
+
+use crate::store::{Snippet, history};
+
+/// Days after which a use counts half as much as a use today
+const HALF_LIFE_DAYS: f64 = 7.0;
+
+/// Where ono is run, uses recorded in the same place rank higher
+#[derive(Debug, Default)]
+pub struct Context {
+    pub cwd: Option<PathBuf>,
+    pub git_root: Option<PathBuf>,
+}
+
+impl Context {
+    pub fn current() -> Self {
+        let cwd = env::current_dir().ok();
+        Context {
+            git_root: cwd.as_deref().and_then(history::find_git_root),
+            cwd,
+        }
+    }
+}
+
+/// Sort the snippets by their uses in the context, then by their recent uses anywhere,
+/// then by `used` and `last_used`.
+/// A use in the same directory counts twice as much as a use in the same project.
+pub fn rank(snippets: &mut [Snippet], history: &[history::Record], context: &Context) {
+    let now = Local::now();
+    let mut context_scores: HashMap<&str, u32> = HashMap::new();
+    let mut frecency: HashMap<&str, f64> = HashMap::new();
+    for record in history {
+        let id = record.id.as_str();
+        let same_dir = context.cwd.is_some() && record.cwd == context.cwd;
+        let same_project = context.git_root.is_some() && record.git_root == context.git_root;
+        let score = 2 * u32::from(same_dir) + u32::from(same_project);
+        if score > 0 {
+            *context_scores.entry(id).or_default() += score;
+        }
+        *frecency.entry(id).or_default() += weight(record.time, now);
+    }
+
+    let context_score = |s: &Snippet| context_scores.get(s.id.as_str()).copied();
+    let frecency = |s: &Snippet| frecency.get(s.id.as_str()).copied().unwrap_or_default();
+    snippets.sort_by(|a, b| {
+        context_score(b)
+            .cmp(&context_score(a))
+            .then_with(|| {
+                frecency(b)
+                    .partial_cmp(&frecency(a))
+                    .unwrap_or(Ordering::Equal)
+            })
+            .then_with(|| {
+                (Reverse(a.used), Reverse(a.last_used))
+                    .cmp(&(Reverse(b.used), Reverse(b.last_used)))
+            })
+    });
+}
+
+/// A use counts less the older it is
+fn weight(time: DateTime<Local>, now: DateTime<Local>) -> f64 {
+    let age_days = (now - time).num_minutes().max(0) as f64 / (24.0 * 60.0);
+    0.5_f64.powf(age_days / HALF_LIFE_DAYS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+
+    #[test]
+    fn it_ranks_by_context_then_usage() {
+        let snippet = |name: &str, used: u32| Snippet {
+            id: name.to_string(),
+            title: name.to_string(),
+            path: PathBuf::from(format!("/data/{name}.md")),
+            used,
+            ..Default::default()
+        };
+        let record = |name: &str, cwd: &str, git_root: Option<&str>| history::Record {
+            time: Local::now(),
+            id: name.to_string(),
+            path: PathBuf::from(format!("/data/{name}.md")),
+            cwd: Some(PathBuf::from(cwd)),
+            git_root: git_root.map(PathBuf::from),
+            exit_status: None,
+            variables: vec![],
+        };
+
+        let mut snippets = vec![
+            snippet("popular", 50),
+            snippet("project", 1),
+            snippet("unused", 0),
+            snippet("here", 0),
+            snippet("elsewhere", 2),
+        ];
+        let history = vec![
+            record("project", "/src/ono/docs", Some("/src/ono")),
+            record("here", "/src/ono/src", Some("/src/ono")),
+            record("elsewhere", "/src/other", Some("/src/other")),
+            record("elsewhere", "/tmp", None),
+        ];
+        let context = Context {
+            cwd: Some(PathBuf::from("/src/ono/src")),
+            git_root: Some(PathBuf::from("/src/ono")),
+        };
+
+        rank(&mut snippets, &history, &context);
+
+        let titles: Vec<&str> = snippets.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            vec!["here", "project", "elsewhere", "popular", "unused"]
+        );
+    }
+
+    #[test]
+    fn it_weights_recent_uses_more() {
+        let now = Local::now();
+        assert_eq!(weight(now, now), 1.0);
+        assert!((weight(now - chrono::Duration::days(7), now) - 0.5).abs() < 1e-9);
+        assert!(weight(now - chrono::Duration::days(30), now) < 0.1);
+    }
+}
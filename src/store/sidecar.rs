@@ -0,0 +1,175 @@
+//! Usage counters and ids of snippets in read-only data directories.
+//! Shared files are never touched, both are kept in local files, one snippet per line.
+//! Usage lines are keyed by snippet id, older lines by the path of the file.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local};
+
+use crate::{os_helper, store::atomic_file};
+
+static SIDECAR_FILE: &str = "readonly_usage.tsv";
+static IDS_FILE: &str = "readonly_ids.tsv";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Usage {
+    pub used: u32,
+    pub last_used: Option<DateTime<Local>>,
+}
+
+/// Id given to a read-only file without `id`, the content hash recognizes the file when it's moved
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadOnlyId {
+    pub id: String,
+    pub hash: u64,
+}
+
+pub fn get_sidecar_path() -> PathBuf {
+    os_helper::get_state_dir().join(SIDECAR_FILE)
+}
+
+pub fn get_ids_path() -> PathBuf {
+    os_helper::get_state_dir().join(IDS_FILE)
+}
+
+/// Load usage counters keyed by snippet id, missing file means no usage recorded yet
+pub fn load_usage(path: &Path) -> HashMap<String, Usage> {
+    fs::read_to_string(path)
+        .map(|content| parse_usage(&content))
+        .unwrap_or_default()
+}
+
+pub fn save_usage(path: &Path, usage: &HashMap<String, Usage>) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    atomic_file::write_atomic(path, &format_usage(usage)).map(|_| ())
+}
+
+/// Load the ids of read-only files keyed by path, missing file means none are given yet
+pub fn load_ids(path: &Path) -> HashMap<PathBuf, ReadOnlyId> {
+    fs::read_to_string(path)
+        .map(|content| parse_ids(&content))
+        .unwrap_or_default()
+}
+
+pub fn save_ids(path: &Path, ids: &HashMap<PathBuf, ReadOnlyId>) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    atomic_file::write_atomic(path, &format_ids(ids)).map(|_| ())
+}
+
+fn parse_ids(content: &str) -> HashMap<PathBuf, ReadOnlyId> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            let id = parts.next().filter(|id| !id.is_empty())?.to_string();
+            let hash = u64::from_str_radix(parts.next()?, 16).ok()?;
+            let path = PathBuf::from(parts.next()?);
+
+            Some((path, ReadOnlyId { id, hash }))
+        })
+        .collect()
+}
+
+fn format_ids(ids: &HashMap<PathBuf, ReadOnlyId>) -> String {
+    let mut lines: Vec<String> = ids
+        .iter()
+        .map(|(path, entry)| format!("{}\t{:016x}\t{}\n", entry.id, entry.hash, path.display()))
+        .collect();
+
+    lines.sort();
+    lines.concat()
+}
+
+fn parse_usage(content: &str) -> HashMap<String, Usage> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            let used = parts.next()?.parse().ok()?;
+            let last_used = DateTime::parse_from_rfc3339(parts.next()?)
+                .ok()
+                .map(|d| d.with_timezone(&Local));
+            let key = parts.next()?.to_string();
+
+            Some((key, Usage { used, last_used }))
+        })
+        .collect()
+}
+
+fn format_usage(usage: &HashMap<String, Usage>) -> String {
+    let mut lines: Vec<String> = usage
+        .iter()
+        .map(|(key, u)| {
+            let last_used = u.last_used.map(|d| d.to_rfc3339()).unwrap_or_default();
+            format!("{}\t{last_used}\t{key}\n", u.used)
+        })
+        .collect();
+
+    lines.sort();
+    lines.concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_round_trips_usage() {
+        let last_used = DateTime::parse_from_rfc3339("2025-12-31T17:00:00+00:00")
+            .unwrap()
+            .with_timezone(&Local);
+        let mut usage = HashMap::new();
+        usage.insert(
+            "6f2b0c1d9e8a7b3c".to_string(),
+            Usage {
+                used: 3,
+                last_used: Some(last_used),
+            },
+        );
+        // older lines are keyed by path
+        usage.insert("/srv/team/ls.md".to_string(), Usage::default());
+
+        let parsed = parse_usage(&format_usage(&usage));
+        assert_eq!(parsed, usage);
+    }
+
+    #[test]
+    fn it_round_trips_ids() {
+        let mut ids = HashMap::new();
+        ids.insert(
+            PathBuf::from("/srv/team/disk\tfree.md"),
+            ReadOnlyId {
+                id: "6f2b0c1d9e8a7b3c".to_string(),
+                hash: 0x00ff_0000_0000_0001,
+            },
+        );
+        ids.insert(
+            PathBuf::from("/srv/team/ls.md"),
+            ReadOnlyId {
+                id: "0123456789abcdef".to_string(),
+                hash: u64::MAX,
+            },
+        );
+
+        let parsed = parse_ids(&format_ids(&ids));
+        assert_eq!(parsed, ids);
+        assert!(parse_ids("not an id line\nabc\tnot hex\t/srv/a.md\n").is_empty());
+    }
+
+    #[test]
+    fn it_skips_malformed_lines() {
+        let parsed = parse_usage("not a usage line\nfoo\t\t/srv/a.md\n2\t\t/srv/b.md\n");
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed["/srv/b.md"].used, 2);
+    }
+}
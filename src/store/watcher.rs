@@ -0,0 +1,50 @@
+//! Watches the data directories so changes made in other programs show up while ono is open
+
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::config::DataDir;
+
+/// Start watching the data directories and their folders, `on_change` is called with the path
+/// of each changed markdown file. Renames report both the old and the new path.
+/// Watching stops when the watcher is dropped.
+pub fn watch_data_dirs(
+    data_dirs: &[DataDir],
+    on_change: impl Fn(PathBuf) + Send + 'static,
+) -> notify::Result<RecommendedWatcher> {
+    let mut watcher =
+        notify::recommended_watcher(move |result: notify::Result<Event>| match result {
+            Ok(event) if is_change(&event.kind) => {
+                let md_extension = Some(OsStr::new("md"));
+                for path in event
+                    .paths
+                    .into_iter()
+                    .filter(|p| p.extension() == md_extension)
+                {
+                    on_change(path);
+                }
+            }
+            Ok(_) => {}
+            Err(err) => log::warn!("Error occurred while watching data directories: {err:?}"),
+        })?;
+
+    for data_dir in data_dirs {
+        let path = Path::new(&data_dir.path);
+        if path.is_dir() {
+            watcher.watch(path, RecursiveMode::Recursive)?;
+        }
+    }
+
+    Ok(watcher)
+}
+
+fn is_change(kind: &EventKind) -> bool {
+    matches!(
+        kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    )
+}
@@ -0,0 +1,239 @@
+//! Obsidian style `[[wikilinks]]` in the notes of a data directory, rewritten when a
+//! snippet file is renamed or moved so the links keep pointing to it.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::store::atomic_file;
+
+/// Rewrite the links to the note at `old_path` in every markdown file of the vault.
+/// The note paths are relative to the vault, as the folders in the links are.
+/// Returns the files that are changed, files that can't be updated are logged and skipped.
+pub fn rewrite_links_in_dir(
+    vault: &Path,
+    old_path: &Path,
+    new_path: &Path,
+) -> io::Result<Vec<PathBuf>> {
+    let mut updated = vec![];
+    for path in list_notes(vault)? {
+        let result = atomic_file::read_stamped(&path).and_then(|(content, stamp)| {
+            match rewrite_links(&content, old_path, new_path) {
+                Some(content) => {
+                    atomic_file::write_if_unchanged(&path, &content, Some(stamp)).map(|_| true)
+                }
+                None => Ok(false),
+            }
+        });
+
+        match result {
+            Ok(true) => updated.push(path),
+            Ok(false) => {}
+            Err(err) => log::warn!("Couldn't update the links in {path:?}: {err}"),
+        }
+    }
+
+    Ok(updated)
+}
+
+/// Notes of the vault with links to the note at `path`, relative to the vault.
+/// Files that can't be read are logged and skipped.
+pub fn find_links_in_dir(vault: &Path, path: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut result = vec![];
+    for note in list_notes(vault)? {
+        match fs::read_to_string(&note) {
+            Ok(content) if replace_links(&content, path, path).is_some() => result.push(note),
+            Ok(_) => {}
+            Err(err) => log::warn!("Couldn't read the links in {note:?}: {err}"),
+        }
+    }
+
+    Ok(result)
+}
+
+/// Markdown files in the directory and its subdirectories,
+/// hidden ones like `.obsidian` are skipped
+fn list_notes(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut result = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_hidden = path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with('.'));
+        if is_hidden {
+            continue;
+        }
+
+        if path.is_dir() {
+            result.extend(list_notes(&path)?);
+        } else if path.extension().is_some_and(|e| e == "md") {
+            result.push(path);
+        }
+    }
+
+    result.sort();
+    Ok(result)
+}
+
+/// Rewrite the links to the note at `old_path` to point to `new_path`, `None` if there are none.
+/// Paths are relative to the vault. Links are matched by note name ignoring case as in Obsidian,
+/// a link with folders matches when they're the last folders of the old path and it gets all
+/// the folders of the new path. Headings, block references and display texts of the links
+/// are kept.
+pub fn rewrite_links(content: &str, old_path: &Path, new_path: &Path) -> Option<String> {
+    replace_links(content, old_path, new_path)
+        .filter(|(_, changed)| *changed)
+        .map(|(content, _)| content)
+}
+
+/// Content with the links to `old_path` pointing to `new_path` and whether it's changed,
+/// `None` if there are no links to `old_path`
+fn replace_links(content: &str, old_path: &Path, new_path: &Path) -> Option<(String, bool)> {
+    let old_name = old_path.file_stem()?.to_string_lossy().to_lowercase();
+    let new_name = new_path.file_stem()?.to_string_lossy();
+    let old_folders = folders(old_path);
+    let new_folders = folders(new_path);
+
+    let mut result = String::with_capacity(content.len());
+    let mut rest = content;
+    let mut matched = false;
+    let mut changed = false;
+    while let Some(start) = rest.find("[[") {
+        let (before, link) = rest.split_at(start + 2);
+        result.push_str(before);
+        let Some(end) = link.find("]]").filter(|&end| !link[..end].contains('\n')) else {
+            rest = link;
+            continue;
+        };
+
+        // target ends at the heading or the display text, `\|` is used in tables
+        let inner = &link[..end];
+        let target_end = inner.find(['#', '|']).unwrap_or(inner.len());
+        let target = inner[..target_end].trim_end_matches('\\');
+        let (folder_part, name) = match target.rsplit_once('/') {
+            Some((folder_part, name)) => (Some(folder_part), name),
+            None => (None, target),
+        };
+        let (name, extension) = match name.strip_suffix(".md") {
+            Some(name) => (name, ".md"),
+            None => (name, ""),
+        };
+
+        let link_folders: Vec<&str> = folder_part.map_or(vec![], |f| f.split('/').collect());
+        let is_match = name.to_lowercase() == old_name && ends_with(&old_folders, &link_folders);
+        if is_match {
+            let new_target = match folder_part {
+                Some(_) if !new_folders.is_empty() => {
+                    format!("{}/{new_name}{extension}", new_folders.join("/"))
+                }
+                _ => format!("{new_name}{extension}"),
+            };
+            result.push_str(&new_target);
+            result.push_str(&inner[target.len()..]);
+            matched = true;
+            changed |= new_target != target;
+        } else {
+            result.push_str(inner);
+        }
+
+        result.push_str("]]");
+        rest = &link[end + 2..];
+    }
+
+    result.push_str(rest);
+    matched.then_some((result, changed))
+}
+
+fn ends_with(folders: &[String], tail: &[&str]) -> bool {
+    folders.len() >= tail.len()
+        && folders[folders.len() - tail.len()..]
+            .iter()
+            .zip(tail)
+            .all(|(folder, t)| folder == t)
+}
+
+/// Names of the folders the file is in, outermost first
+fn folders(path: &Path) -> Vec<String> {
+    path.parent()
+        .map(|p| {
+            p.components()
+                .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn it_rewrites_links() {
+        let old_path = Path::new("tools/docker/prune.md");
+        let new_path = Path::new("cleanup/docker prune.md");
+        let test_cases = vec![
+            ("See [[prune]].", Some("See [[docker prune]].")),
+            ("[[Prune|clean up]]", Some("[[docker prune|clean up]]")),
+            ("![[prune#Usage]]", Some("![[docker prune#Usage]]")),
+            (
+                "| [[prune\\|alias]] |",
+                Some("| [[docker prune\\|alias]] |"),
+            ),
+            ("[[docker/prune.md]]", Some("[[cleanup/docker prune.md]]")),
+            ("[[tools/docker/prune]]", Some("[[cleanup/docker prune]]")),
+            // another note with the same name
+            ("[[git/prune]]", None),
+            ("[[vault/tools/docker/prune]]", None),
+            ("[[prune all]] [[other]]", None),
+            ("[[prune\n]] [[", None),
+            ("no links", None),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(
+                rewrite_links(input, old_path, new_path).as_deref(),
+                expected,
+                "input: {input:?}"
+            );
+        }
+
+        // moved to the root of the vault, or only to another folder
+        let root_path = Path::new("prune.md");
+        assert_eq!(
+            rewrite_links("[[docker/prune]]", old_path, root_path).as_deref(),
+            Some("[[prune]]")
+        );
+        assert_eq!(rewrite_links("[[prune]]", old_path, root_path), None);
+    }
+
+    #[test]
+    fn it_rewrites_links_in_notes() {
+        let dir = TestDir::new("wikilink");
+        fs::create_dir_all(dir.join("notes")).unwrap();
+        fs::create_dir_all(dir.join(".obsidian")).unwrap();
+        fs::write(dir.join("notes/docker.md"), "Run [[prune]] weekly\n").unwrap();
+        fs::write(dir.join("ls.md"), "```\nls\n```\n").unwrap();
+        fs::write(dir.join(".obsidian/prune.md"), "[[prune]]").unwrap();
+
+        fs::write(dir.join("notes/git.md"), "Not [[notes/prune]]\n").unwrap();
+
+        let updated = rewrite_links_in_dir(
+            &dir,
+            Path::new("prune.md"),
+            Path::new("docker/Docker prune.md"),
+        )
+        .unwrap();
+
+        assert_eq!(updated, vec![dir.join("notes/docker.md")]);
+        assert_eq!(
+            fs::read_to_string(dir.join("notes/docker.md")).unwrap(),
+            "Run [[Docker prune]] weekly\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join(".obsidian/prune.md")).unwrap(),
+            "[[prune]]"
+        );
+    }
+}
@@ -1,6 +1,8 @@
 use std::io::{self, Stdout, stdout};
 
 pub mod app;
+pub mod event;
+pub mod form;
 mod widgets;
 
 use ratatui::{
@@ -26,6 +28,11 @@ pub fn init() -> io::Result<Tui> {
 fn set_panic_hook() {
     let hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |panic_info| {
+        // caught and reported in the app, printing it would mess up the screen
+        if std::thread::current().name() == Some(event::TASK_THREAD) {
+            log::error!("Task panicked: {panic_info}");
+            return;
+        }
         let _ = restore(); // ignore any errors as we are already failing
         hook(panic_info);
     }));
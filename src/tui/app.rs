@@ -1,18 +1,27 @@
+use std::{io, path::PathBuf, sync::mpsc::Sender, time::Duration};
+
 use color_eyre::{Result, eyre::WrapErr};
 
 use crossterm::event::KeyModifiers;
 use ratatui::{
     Frame,
-    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind},
     layout::{Constraint, Direction, Layout},
     widgets::ListState,
 };
 
 use crate::{
-    store::Snippet,
+    clipboard,
+    config::DataDir,
+    store::{self, Duplicate, Snippet, danger, history, rank},
     tui::{
         self,
-        widgets::{SearchWidget, SnippetDetailWidget, SnippetListWidget, TopWidget},
+        event::{self, AppEvent, EventLoop, TaskResult},
+        form::{FormAction, RenameForm, SnippetForm},
+        widgets::{
+            RenameFormWidget, SearchWidget, SnippetDetailWidget, SnippetFormWidget,
+            SnippetListWidget, TopWidget,
+        },
     },
 };
 
@@ -22,8 +31,36 @@ pub struct App {
 
     pub snippets: Vec<Snippet>,
     pub list_state: ListState,
+    /// Index of the snippet picked with r, run once the TUI is closed
+    pub selected: Option<usize>,
+    /// New snippet form, shown instead of the snippet detail when set
+    pub form: Option<SnippetForm>,
+    /// Rename form of the selected snippet, shown instead of the snippet detail when set
+    pub rename_form: Option<RenameForm>,
+    /// Directory new snippets are written to
+    pub data_dir: Option<String>,
+    pub data_dirs: Vec<DataDir>,
+    /// Danger rules from the config, checked along with the default ones
+    pub danger_rules: Vec<danger::Rule>,
+    pub status: Option<String>,
+
+    /// Sends the results of background tasks, set while the app is running
+    pub events: Option<Sender<AppEvent>>,
+    /// Descriptions of the background tasks still running
+    pub tasks: Vec<&'static str>,
+    /// Files changed while the snippets are loading, reloaded once they're loaded
+    pub pending_changes: Vec<PathBuf>,
+    pub tick: usize,
 }
 
+/// How long to wait for input before sending a tick
+const TICK_RATE: Duration = Duration::from_millis(100);
+
+const LOADING_TASK: &str = "Loading snippets";
+const COPY_TASK: &str = "Copying to clipboard";
+
+const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+
 enum ListMoveDirection {
     Up,
     Down,
@@ -32,13 +69,70 @@ enum ListMoveDirection {
 impl App {
     /// runs the application's main loop until the user quits
     pub fn run(&mut self, terminal: &mut tui::Tui) -> Result<()> {
+        let events = EventLoop::new(TICK_RATE);
+        self.events = Some(events.sender());
+
+        // the watcher stops when dropped, keep it until the app exits
+        let sender = events.sender();
+        let _watcher = store::watcher::watch_data_dirs(&self.data_dirs, move |path| {
+            let _ = sender.send(AppEvent::FileChanged(path));
+        })
+        .inspect_err(|err| {
+            log::warn!("Data directories can't be watched, changes won't be reloaded: {err:?}")
+        })
+        .ok();
+
+        self.load_snippets();
+
         while !self.exit {
             terminal.draw(|frame| self.render_frame(frame))?;
-            self.handle_events().wrap_err("handle events failed")?;
+
+            self.handle_event(events.next()?)
+                .wrap_err("handle events failed")?;
+            // handle everything that piled up while drawing before drawing again
+            for app_event in events.pending() {
+                if self.exit {
+                    break;
+                }
+                self.handle_event(app_event)
+                    .wrap_err("handle events failed")?;
+            }
         }
+
+        self.events = None;
         Ok(())
     }
 
+    /// Run the task in a worker thread, or right away if the app isn't running
+    fn spawn_task<F>(&mut self, description: &'static str, task: F)
+    where
+        F: FnOnce() -> TaskResult + Send + 'static,
+    {
+        match &self.events {
+            Some(sender) => {
+                self.tasks.push(description);
+                event::spawn_task(sender, description, task);
+            }
+            None => self.handle_task_result(task()),
+        }
+    }
+
+    fn load_snippets(&mut self) {
+        if self.data_dirs.is_empty() {
+            return;
+        }
+
+        let data_dirs = self.data_dirs.clone();
+        let danger_rules = self.danger_rules.clone();
+        self.spawn_task(LOADING_TASK, move || {
+            let mut loaded = store::load_snippets(&data_dirs);
+            danger::flag_snippets(&mut loaded.snippets, &danger_rules);
+            let history = history::load(&history::get_history_path());
+            rank::rank(&mut loaded.snippets, &history, &rank::Context::current());
+            TaskResult::SnippetsLoaded(loaded)
+        });
+    }
+
     fn render_frame(&self, frame: &mut Frame) {
         let outer_layout = Layout::default()
             .direction(Direction::Vertical)
@@ -64,35 +158,156 @@ impl App {
             inner_layout[0],
         );
 
+        if let Some(form) = &self.form {
+            frame.render_widget(&SnippetFormWidget { form }, inner_layout[1]);
+        } else if let Some(form) = &self.rename_form {
+            frame.render_widget(&RenameFormWidget { form }, inner_layout[1]);
+        } else {
+            frame.render_widget(
+                &SnippetDetailWidget {
+                    snippet: self.list_state.selected().map(|idx| &self.snippets[idx]),
+                },
+                inner_layout[1],
+            );
+        }
+
+        let status = self.status_text();
         frame.render_widget(
-            &SnippetDetailWidget {
-                snippet: self.list_state.selected().map(|idx| &self.snippets[idx]),
+            &SearchWidget {
+                query: "",
+                status: status.as_deref(),
             },
-            inner_layout[1],
+            outer_layout[2],
         );
+    }
 
-        frame.render_widget(&SearchWidget { query: "" }, outer_layout[2]);
+    /// Running tasks take precedence over the last status message
+    fn status_text(&self) -> Option<String> {
+        match self.tasks.last() {
+            Some(task) => Some(format!("{} {task}", SPINNER[self.tick % SPINNER.len()])),
+            None => self.status.clone(),
+        }
     }
 
-    /// updates the application's state based on user input
-    fn handle_events(&mut self) -> Result<()> {
-        match event::read()? {
+    /// updates the application's state based on the event
+    fn handle_event(&mut self, app_event: AppEvent) -> Result<()> {
+        match app_event {
             // it's important to check that the event is a key press event as
             // crossterm also emits key release and repeat events on Windows.
-            Event::Key(key_event) if key_event.kind == KeyEventKind::Press => self
+            AppEvent::Input(Event::Key(key_event)) if key_event.kind == KeyEventKind::Press => self
                 .handle_key_event(key_event)
                 .wrap_err_with(|| format!("handling key event failed:\n{key_event:#?}")),
-            _ => Ok(()),
+            AppEvent::Input(_) => Ok(()),
+            AppEvent::Tick => {
+                self.tick = self.tick.wrapping_add(1);
+                Ok(())
+            }
+            AppEvent::FileChanged(path) => {
+                self.handle_file_changes(vec![path]);
+                Ok(())
+            }
+            AppEvent::TaskDone(result) => {
+                self.handle_task_result(result);
+                Ok(())
+            }
         }
     }
 
+    fn handle_task_result(&mut self, result: TaskResult) {
+        match result {
+            TaskResult::SnippetsLoaded(loaded) => {
+                self.finish_task(LOADING_TASK);
+                self.snippets = loaded.snippets;
+                self.list_state
+                    .select((!self.snippets.is_empty()).then_some(0));
+                let changed = std::mem::take(&mut self.pending_changes);
+                self.handle_file_changes(changed);
+
+                self.status = match loaded.warnings.as_slice() {
+                    [] => None,
+                    [warning] => Some(format!("Couldn't load {warning}")),
+                    [warning, rest @ ..] => Some(format!(
+                        "Couldn't load {warning} and {} more files",
+                        rest.len()
+                    )),
+                };
+            }
+            TaskResult::Failed(description) => {
+                self.finish_task(description);
+                self.status = Some(format!("{description} failed"));
+            }
+            TaskResult::Copied { command, result } => {
+                self.finish_task(COPY_TASK);
+                let result = result.or_else(|err| {
+                    log::warn!("Clipboard helper failed, using OSC 52: {err:?}");
+                    clipboard::write_osc52(&command)
+                });
+                self.show_copy_result(result);
+            }
+        }
+    }
+
+    fn finish_task(&mut self, description: &str) {
+        if let Some(idx) = self.tasks.iter().position(|t| *t == description) {
+            self.tasks.remove(idx);
+        }
+    }
+
+    /// Reload the snippets changed on disk, keeping the selected snippet selected
+    fn handle_file_changes(&mut self, changed: Vec<PathBuf>) {
+        if changed.is_empty() {
+            return;
+        }
+
+        // the snippets being loaded may miss the change
+        if self.tasks.contains(&LOADING_TASK) {
+            self.pending_changes.extend(changed);
+            return;
+        }
+
+        let selected = self.list_state.selected();
+        let selected_id = selected.map(|idx| self.snippets[idx].id.clone());
+        for path in changed {
+            if let Err(err) = store::reload_snippet(&mut self.snippets, &self.data_dirs, &path) {
+                log::error!("Error occurred while reloading {path:?}: {err:?}");
+            }
+        }
+        danger::flag_snippets(&mut self.snippets, &self.danger_rules);
+
+        let new_index = selected_id
+            .and_then(|id| self.snippets.iter().position(|s| s.id == id))
+            .or_else(|| selected.map(|idx| idx.min(self.snippets.len().saturating_sub(1))));
+        self.list_state
+            .select(new_index.filter(|_| !self.snippets.is_empty()));
+    }
+
     fn handle_key_event(&mut self, key_event: KeyEvent) -> Result<()> {
-        match key_event.code {
-            KeyCode::Char('c') | KeyCode::Char('C')
-                if key_event.modifiers == KeyModifiers::CONTROL =>
-            {
-                self.exit()
+        let is_exit = matches!(key_event.code, KeyCode::Char('c') | KeyCode::Char('C'))
+            && key_event.modifiers == KeyModifiers::CONTROL;
+
+        if !is_exit && let Some(form) = &mut self.form {
+            match form.handle_key_event(key_event) {
+                FormAction::Submit => self.save_form(),
+                FormAction::Cancel => self.form = None,
+                FormAction::None => {}
+            }
+            return Ok(());
+        }
+
+        if !is_exit && let Some(form) = &mut self.rename_form {
+            match form.handle_key_event(key_event) {
+                FormAction::Submit => self.save_rename_form(),
+                FormAction::Cancel => self.rename_form = None,
+                FormAction::None => {}
             }
+            return Ok(());
+        }
+
+        match key_event.code {
+            _ if is_exit => self.exit(),
+            KeyCode::Char('y') => self.copy_selected(),
+            KeyCode::Char('r') => self.select(),
+            KeyCode::Char('m') => self.open_rename_form(),
             KeyCode::Up => self.move_list_selection(ListMoveDirection::Up),
             KeyCode::Down => self.move_list_selection(ListMoveDirection::Down),
             _ => {}
@@ -104,6 +319,148 @@ impl App {
         self.exit = true;
     }
 
+    fn select(&mut self) {
+        if let Some(idx) = self.list_state.selected() {
+            self.selected = Some(idx);
+            self.exit();
+        }
+    }
+
+    /// Copy with the clipboard helper in the background as it can be slow to respond,
+    /// OSC 52 is written here between frames so it doesn't end up in the middle of one
+    fn copy_selected(&mut self) {
+        let Some(snippet) = self.list_state.selected().map(|idx| &self.snippets[idx]) else {
+            return;
+        };
+
+        let command = snippet.command.clone();
+        if clipboard::has_copy_helper() {
+            self.spawn_task(COPY_TASK, move || {
+                let result = clipboard::write_with_helper(&command);
+                TaskResult::Copied { command, result }
+            });
+        } else {
+            let result = clipboard::write_osc52(&command);
+            self.show_copy_result(result);
+        }
+    }
+
+    fn show_copy_result(&mut self, result: io::Result<()>) {
+        self.status = match result {
+            Ok(()) => Some("Copied to clipboard".to_string()),
+            Err(err) => {
+                log::error!("Error occurred while copying to clipboard: {err:?}");
+                Some(format!("Copy failed: {err}"))
+            }
+        };
+    }
+
+    fn save_form(&mut self) {
+        let Some(form) = &mut self.form else {
+            return;
+        };
+
+        let Some(data_dir) = &self.data_dir else {
+            form.error = Some("There is no writable data directory".to_string());
+            return;
+        };
+
+        // saved when Enter is pressed again, unless the secrets are replaced
+        if !form.confirm_secrets() {
+            form.error = Some("Press Enter again to save it with the secrets".to_string());
+            return;
+        }
+
+        let new_snippet = form.to_new_snippet();
+        match store::find_duplicate(&self.snippets, &new_snippet) {
+            Some(Duplicate::Title(_)) => {
+                form.error = Some(format!("Title `{}` is already used", new_snippet.title));
+                return;
+            }
+            Some(Duplicate::Alias(existing)) => {
+                form.error = Some(format!("Alias is already used by `{}`", existing.title));
+                return;
+            }
+            None => {}
+        }
+
+        let snippet = match store::create_snippet(data_dir, &new_snippet) {
+            Ok(snippet) => snippet,
+            Err(err) => {
+                form.error = Some(err.to_string());
+                return;
+            }
+        };
+        self.status = Some(format!("Saved {}", snippet.title));
+        self.form = None;
+
+        // added as if the watcher reported it, so aliases are resolved and dangers flagged
+        if let Err(err) = store::reload_snippet(&mut self.snippets, &self.data_dirs, &snippet.path)
+        {
+            log::error!("Error occurred while loading {:?}: {err:?}", snippet.path);
+        }
+        if let Some(idx) = self.snippets.iter().position(|s| s.path == snippet.path) {
+            danger::flag_snippets(&mut self.snippets[idx..=idx], &self.danger_rules);
+            self.list_state.select(Some(idx));
+        }
+    }
+
+    fn open_rename_form(&mut self) {
+        let Some(snippet) = self.list_state.selected().map(|idx| &self.snippets[idx]) else {
+            return;
+        };
+        if snippet.read_only {
+            self.status = Some(format!("{} is in a read-only directory", snippet.title));
+            return;
+        }
+
+        let folders = store::list_folders(&self.data_dirs);
+        self.rename_form = Some(RenameForm::new(
+            &snippet.title,
+            folders,
+            snippet.path.parent(),
+        ));
+    }
+
+    fn save_rename_form(&mut self) {
+        let (Some(form), Some(idx)) = (&mut self.rename_form, self.list_state.selected()) else {
+            return;
+        };
+        let Some(folder) = form.folders.get(form.folder) else {
+            form.error = Some("There is no writable data directory".to_string());
+            return;
+        };
+
+        let snippet = &self.snippets[idx];
+        if let Some(existing) = store::find_title_clash(&self.snippets, snippet, &form.title) {
+            form.error = Some(format!("Title is already used by `{}`", existing.title));
+            return;
+        }
+
+        match store::move_snippet(snippet, &self.data_dirs, &folder.path, &form.title) {
+            Ok(moved) => {
+                let mut status = format!("Renamed to {}", moved.snippet.title);
+                if !moved.updated_notes.is_empty() {
+                    status.push_str(&format!(
+                        ", updated links in {} notes",
+                        moved.updated_notes.len()
+                    ));
+                }
+                if !moved.stale_notes.is_empty() {
+                    status.push_str(&format!(
+                        ", {} notes still link to the old data directory",
+                        moved.stale_notes.len()
+                    ));
+                }
+                self.status = Some(status);
+                self.snippets[idx] = moved.snippet;
+                danger::flag_snippets(&mut self.snippets[idx..=idx], &self.danger_rules);
+                self.rename_form = None;
+            }
+            Err(err) => form.error = Some(err.to_string()),
+        }
+    }
+
     fn move_list_selection(&mut self, direction: ListMoveDirection) {
         if self.snippets.is_empty() {
             return;
@@ -120,3 +477,66 @@ impl App {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        store::{LoadedSnippets, NewSnippet},
+        test_dir::TestDir,
+    };
+
+    #[test]
+    fn it_defers_file_changes_while_loading() {
+        let mut app = App {
+            tasks: vec![LOADING_TASK],
+            ..Default::default()
+        };
+
+        app.handle_event(AppEvent::FileChanged(PathBuf::from("/tmp/a.md")))
+            .unwrap();
+        assert_eq!(app.pending_changes, vec![PathBuf::from("/tmp/a.md")]);
+        assert!(app.status_text().unwrap().ends_with(LOADING_TASK));
+
+        let snippets = vec![Snippet {
+            title: "a".to_string(),
+            ..Default::default()
+        }];
+        let loaded = LoadedSnippets {
+            snippets,
+            ..Default::default()
+        };
+        app.handle_event(AppEvent::TaskDone(TaskResult::SnippetsLoaded(loaded)))
+            .unwrap();
+        assert!(app.tasks.is_empty());
+        assert!(app.pending_changes.is_empty());
+        assert_eq!(app.list_state.selected(), Some(0));
+        assert_eq!(app.status_text(), None);
+    }
+
+    #[test]
+    fn it_flags_saved_snippets() {
+        let dir = TestDir::new("app-save");
+        let data_dir = dir.to_string_lossy().into_owned();
+        let mut app = App {
+            data_dir: Some(data_dir.clone()),
+            data_dirs: vec![DataDir {
+                path: data_dir,
+                label: None,
+                read_only: false,
+            }],
+            form: Some(SnippetForm::new(&NewSnippet {
+                title: "clean".to_string(),
+                command: "rm -rf build".to_string(),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+
+        app.save_form();
+
+        assert!(app.form.is_none());
+        assert_eq!(app.list_state.selected(), Some(0));
+        assert!(app.snippets[0].is_dangerous());
+    }
+}
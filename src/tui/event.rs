@@ -0,0 +1,153 @@
+use std::{
+    io,
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use ratatui::crossterm::event::{self, Event};
+
+use crate::store::LoadedSnippets;
+
+/// Everything the app reacts to, sent to a single channel by the input thread,
+/// the file watcher and the background tasks
+#[derive(Debug)]
+pub enum AppEvent {
+    Input(Event),
+    /// Sent when there is no input for a tick, drives animations and timeouts
+    Tick,
+    FileChanged(PathBuf),
+    TaskDone(TaskResult),
+}
+
+/// Results of the work done in background threads
+#[derive(Debug)]
+pub enum TaskResult {
+    /// The task with the description panicked
+    Failed(&'static str),
+    SnippetsLoaded(LoadedSnippets),
+    /// The command written with the clipboard helper, OSC 52 is used if it failed
+    Copied {
+        command: String,
+        result: io::Result<()>,
+    },
+}
+
+/// Name of the worker threads running tasks, their panics are reported in the app
+pub const TASK_THREAD: &str = "ono-task";
+
+/// Reads terminal input in a background thread and collects app events
+pub struct EventLoop {
+    sender: Sender<AppEvent>,
+    receiver: Receiver<AppEvent>,
+    tick_rate: Duration,
+    stop: Arc<AtomicBool>,
+    input_thread: Option<JoinHandle<()>>,
+}
+
+impl EventLoop {
+    pub fn new(tick_rate: Duration) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let input_sender = sender.clone();
+        let input_stop = stop.clone();
+        let input_thread = thread::spawn(move || {
+            while !input_stop.load(Ordering::Relaxed) {
+                let app_event = match event::poll(tick_rate) {
+                    Ok(true) => match event::read() {
+                        Ok(e) => AppEvent::Input(e),
+                        Err(err) => {
+                            log::error!("Error occurred while reading input: {err:?}");
+                            break;
+                        }
+                    },
+                    Ok(false) => AppEvent::Tick,
+                    Err(err) => {
+                        log::error!("Error occurred while polling input: {err:?}");
+                        break;
+                    }
+                };
+
+                if input_sender.send(app_event).is_err() {
+                    break;
+                }
+            }
+        });
+
+        EventLoop {
+            sender,
+            receiver,
+            tick_rate,
+            stop,
+            input_thread: Some(input_thread),
+        }
+    }
+
+    /// Sender for the other event sources
+    pub fn sender(&self) -> Sender<AppEvent> {
+        self.sender.clone()
+    }
+
+    /// Wait for the next event.
+    /// The channel stays open while senders are around, so the input thread is checked
+    /// every tick and an error is returned once it has stopped.
+    pub fn next(&self) -> io::Result<AppEvent> {
+        loop {
+            match self.receiver.recv_timeout(self.tick_rate) {
+                Ok(app_event) => return Ok(app_event),
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(io::Error::other("event channel is closed"));
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    let is_stopped = self.input_thread.as_ref().is_none_or(|t| t.is_finished());
+                    if is_stopped {
+                        return Err(io::Error::other("input thread has stopped"));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Take the events that are already waiting, without blocking
+    pub fn pending(&self) -> impl Iterator<Item = AppEvent> + '_ {
+        self.receiver.try_iter()
+    }
+}
+
+impl Drop for EventLoop {
+    fn drop(&mut self) {
+        // input thread stops within a tick, it shouldn't read input meant for the shell
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(input_thread) = self.input_thread.take() {
+            let _ = input_thread.join();
+        }
+    }
+}
+
+/// Run the task in a worker thread, the result is sent as an app event.
+/// `TaskResult::Failed` is sent instead if the task panics or the thread can't be started.
+pub fn spawn_task<F>(sender: &Sender<AppEvent>, description: &'static str, task: F)
+where
+    F: FnOnce() -> TaskResult + Send + 'static,
+{
+    let task_sender = sender.clone();
+    let spawned = thread::Builder::new()
+        .name(TASK_THREAD.to_string())
+        .spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(task))
+                .unwrap_or(TaskResult::Failed(description));
+            let _ = task_sender.send(AppEvent::TaskDone(result));
+        });
+
+    if let Err(err) = spawned {
+        log::error!("Error occurred while starting task `{description}`: {err:?}");
+        let _ = sender.send(AppEvent::TaskDone(TaskResult::Failed(description)));
+    }
+}
@@ -9,7 +9,10 @@ use ratatui::{
     },
 };
 
-use crate::store::Snippet;
+use crate::{
+    store::Snippet,
+    tui::form::{RenameForm, SnippetForm},
+};
 
 pub struct TopWidget {}
 
@@ -22,8 +25,18 @@ pub struct SnippetDetailWidget<'a> {
     pub snippet: Option<&'a Snippet>,
 }
 
+pub struct SnippetFormWidget<'a> {
+    pub form: &'a SnippetForm,
+}
+
+pub struct RenameFormWidget<'a> {
+    pub form: &'a RenameForm,
+}
+
 pub struct SearchWidget<'a> {
     pub query: &'a str,
+    /// Message shown at the right side, e.g. result of the last action
+    pub status: Option<&'a str>,
 }
 
 impl Widget for &TopWidget {
@@ -35,7 +48,13 @@ const SELECTED_STYLE: Style = Style::new().bg(SLATE.c800).add_modifier(Modifier:
 
 impl From<&Snippet> for ListItem<'_> {
     fn from(value: &Snippet) -> Self {
-        let line = Line::styled(format!("- {}", value.title), TEXT_FG_COLOR);
+        let mut line = Line::styled(format!("- {}", value.title), TEXT_FG_COLOR);
+        if value.is_dangerous() {
+            line.push_span(" ⚠".red().bold());
+        }
+        if let Some(source) = &value.source {
+            line.push_span(format!(" [{source}]").dark_gray());
+        }
         // let line = match value.status {
         //     Status::Todo => Line::styled(format!(" ☐ {}", value.todo), TEXT_FG_COLOR),
         //     Status::Completed => {
@@ -68,6 +87,12 @@ impl<'a> Widget for &SnippetDetailWidget<'a> {
             "<↑/↓>".blue().bold(),
             " Select ".into(),
             "<Enter>".blue().bold(),
+            " Copy ".into(),
+            "<y>".blue().bold(),
+            " Run ".into(),
+            "<r>".blue().bold(),
+            " Rename ".into(),
+            "<m>".blue().bold(),
             " Quit ".into(),
             "<Ctrl-C> ".blue().bold(),
         ]);
@@ -81,6 +106,26 @@ impl<'a> Widget for &SnippetDetailWidget<'a> {
                 .padding(Padding::uniform(1));
 
             let mut lines: Vec<Line> = snippet.command.lines().map(Line::from).collect();
+            if snippet.is_dangerous() {
+                let reasons = match snippet.risks.is_empty() {
+                    true => "flagged in the snippet".to_string(),
+                    false => snippet.risks.join(", "),
+                };
+                lines.push(Line::from(""));
+                lines.push(Line::from(format!("⚠ dangerous: {reasons}").red()));
+            }
+
+            let details: Vec<String> = snippet
+                .alias
+                .iter()
+                .map(|alias| format!("alias: {alias}"))
+                .chain(snippet.cwd.iter().map(|cwd| format!("cwd: {cwd}")))
+                .chain(snippet.env.iter().map(|(k, v)| format!("env: {k}={v}")))
+                .collect();
+            if !details.is_empty() {
+                lines.push(Line::from(""));
+                lines.extend(details.into_iter().map(|d| Line::from(d.dark_gray())));
+            }
             if !snippet.description.is_empty() {
                 lines.push(Line::from(""));
                 lines.push(Line::from(snippet.description.as_str().dark_gray()));
@@ -100,8 +145,103 @@ impl<'a> Widget for &SnippetDetailWidget<'a> {
     }
 }
 
+impl<'a> Widget for &SnippetFormWidget<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let instructions = Line::from(vec![
+            " Next ".into(),
+            "<Tab>".blue().bold(),
+            " Save ".into(),
+            "<Enter>".blue().bold(),
+            " Cancel ".into(),
+            "<Esc> ".blue().bold(),
+        ]);
+
+        let block = Block::default()
+            .title(Line::from(" New Snippet ").bold().centered())
+            .title_bottom(instructions.centered())
+            .borders(Borders::ALL)
+            .padding(Padding::uniform(1));
+
+        let mut lines: Vec<Line> = vec![];
+        for (idx, field) in self.form.fields.iter().enumerate() {
+            let label = format!("{:>12}: ", field.label);
+            let line = if idx == self.form.focused {
+                Line::from(vec![
+                    label.blue().bold(),
+                    field.value.as_str().into(),
+                    "█".into(),
+                ])
+            } else {
+                Line::from(vec![label.into(), field.value.as_str().into()])
+            };
+            lines.push(line);
+        }
+
+        if let Some(warning) = self.form.secret_warning() {
+            lines.push(Line::from(""));
+            lines.push(Line::from(vec![
+                format!("⚠ {warning}, ").yellow(),
+                "<Ctrl-R>".blue().bold(),
+                " replaces them with placeholders".yellow(),
+            ]));
+        }
+        if let Some(error) = &self.form.error {
+            lines.push(Line::from(""));
+            lines.push(Line::from(error.as_str().red()));
+        }
+
+        Paragraph::new(Text::from(lines))
+            .block(block)
+            .render(area, buf);
+    }
+}
+
+impl<'a> Widget for &RenameFormWidget<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let instructions = Line::from(vec![
+            " Folder ".into(),
+            "<Tab>".blue().bold(),
+            " Save ".into(),
+            "<Enter>".blue().bold(),
+            " Cancel ".into(),
+            "<Esc> ".blue().bold(),
+        ]);
+
+        let block = Block::default()
+            .title(Line::from(" Rename Snippet ").bold().centered())
+            .title_bottom(instructions.centered())
+            .borders(Borders::ALL)
+            .padding(Padding::uniform(1));
+
+        let mut lines = vec![
+            Line::from(vec![
+                format!("{:>12}: ", "Title").blue().bold(),
+                self.form.title.as_str().into(),
+                "█".into(),
+            ]),
+            Line::from(vec![
+                format!("{:>12}: ", "Folder").into(),
+                self.form.folder_name().into(),
+            ]),
+        ];
+        if let Some(error) = &self.form.error {
+            lines.push(Line::from(""));
+            lines.push(Line::from(error.as_str().red()));
+        }
+
+        Paragraph::new(Text::from(lines))
+            .block(block)
+            .render(area, buf);
+    }
+}
+
 impl<'a> Widget for &SearchWidget<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        Block::default().borders(Borders::ALL).render(area, buf);
+        let mut block = Block::default().borders(Borders::ALL);
+        if let Some(status) = self.status {
+            block = block.title(Line::from(format!(" {status} ")).right_aligned());
+        }
+
+        block.render(area, buf);
     }
 }